@@ -0,0 +1,242 @@
+use crate::{
+    prelude::*,
+    tools::fuzzy,
+};
+
+
+
+/// Most options listed at once; typing narrows the list down
+const MAX_OPTIONS: usize = 50;
+
+
+
+/// An option of the dropdown, or a category heading above a run of options
+#[derive(Clone, Debug, PartialEq)]
+enum Entry {
+    Heading(String),
+    Option { key: String, detail: String },
+    Create(String),
+}
+
+
+
+/// Typeahead picker for the options of a sys database, used by `One` and `Multi` attributes
+///
+/// Typing matches fuzzily across every column of a row. Arrow keys move through the options, Enter picks
+/// one and Escape closes the list. Selections of a `Multi` attribute are shown as removable chips.
+///
+/// # Props
+/// - `id`: The name of the sys database
+/// - `value`: The current value of the field
+/// - `multiple`: Whether several options can be selected
+/// - `on_change`: Receives the new value of the field
+#[component]
+pub fn Combobox(id: String, value: FieldValue, multiple: bool, on_change: EventHandler<FieldValue>) -> Element {
+    let mut query = use_signal(String::new);
+    let mut open = use_signal(|| false);
+    let mut highlighted = use_signal(|| 0usize);
+    let mut message = use_signal(String::new);
+
+    let content = scroll_processor::db_query(&id).map(|v| v.0).unwrap_or_default();
+    let selected = value.items();
+    let entries = entries(&content, &query.read(), &selected, multiple);
+    let choices: Vec<Entry> = entries.iter()
+        .filter(|entry| !matches!(entry, Entry::Heading(_)))
+        .cloned()
+        .collect();
+
+    let pick = {
+        let id = id.clone();
+        let content = content.clone();
+        let selected = selected.clone();
+        move |entry: Entry| {
+            let key = match entry {
+                Entry::Option { key, .. } => key,
+                Entry::Create(key) => {
+                    let mut rows = content.clone();
+                    let mut row = vec![String::new(); rows.first().map_or(1, |header| header.len())];
+                    row[0] = key.clone();
+                    rows.push(row);
+                    if let Err(e) = scroll_processor::write_db(&id, &rows) {
+                        message.set(e.to_string());
+                        return;
+                    }
+                    key
+                },
+                Entry::Heading(_) => return,
+            };
+            let new_value = match multiple {
+                true => {
+                    let mut selections = selected.clone();
+                    if !selections.contains(&key) {
+                        selections.push(key);
+                    }
+                    FieldValue::List(selections)
+                },
+                false => FieldValue::Text(key),
+            };
+            message.set(String::new());
+            query.set(String::new());
+            highlighted.set(0);
+            open.set(multiple);
+            on_change.call(new_value);
+        }
+    };
+
+    rsx! {
+        div {
+            class: "combobox",
+            if !selected.is_empty() {
+                div {
+                    class: "chips",
+                    for item in selected.iter().cloned() {
+                        span {
+                            class: "chip",
+                            "{ item }"
+                            button {
+                                title: "Remove { item }",
+                                onclick: {
+                                    let selected = selected.clone();
+                                    move |_| {
+                                        let remaining: Vec<String> = selected.iter()
+                                            .filter(|other| **other != item)
+                                            .cloned()
+                                            .collect();
+                                        on_change.call(match multiple {
+                                            true => FieldValue::List(remaining),
+                                            false => FieldValue::Text(String::new()),
+                                        });
+                                    }
+                                },
+                                "×"
+                            }
+                        }
+                    }
+                }
+            }
+            input {
+                placeholder: if multiple { "Type to add..." } else { "Type to search..." },
+                value: "{ query.read() }",
+                onfocusin: move |_| open.set(true),
+                onfocusout: move |_| open.set(false),
+                oninput: move |event| {
+                    query.set(event.value());
+                    highlighted.set(0);
+                    open.set(true);
+                },
+                onkeydown: {
+                    let choices = choices.clone();
+                    let mut pick = pick.clone();
+                    move |event: KeyboardEvent| {
+                        let current = *highlighted.read();
+                        match event.key() {
+                            Key::ArrowDown => {
+                                event.prevent_default();
+                                open.set(true);
+                                highlighted.set((current + 1).min(choices.len().saturating_sub(1)));
+                            },
+                            Key::ArrowUp => {
+                                event.prevent_default();
+                                highlighted.set(current.saturating_sub(1));
+                            },
+                            Key::Enter => {
+                                event.prevent_default();
+                                if let Some(entry) = choices.get(current) {
+                                    pick(entry.clone());
+                                }
+                            },
+                            Key::Escape => open.set(false),
+                            _ => (),
+                        }
+                    }
+                },
+            }
+            if *open.read() && !entries.is_empty() {
+                ul {
+                    class: "combobox-options",
+                    for entry in entries.into_iter() {
+                        match entry.clone() {
+                            Entry::Heading(path) => rsx! {
+                                li { class: "combobox-heading", "{ path }" }
+                            },
+                            Entry::Option { key, detail } => {
+                                let active = choices.get(*highlighted.read()) == Some(&entry);
+                                let mut pick = pick.clone();
+                                rsx! {
+                                    li {
+                                        class: if active { "combobox-option active" } else { "combobox-option" },
+                                        // Mouse down fires before the input loses focus and closes the list
+                                        onmousedown: move |event| {
+                                            event.prevent_default();
+                                            pick(entry.clone());
+                                        },
+                                        "{ key }"
+                                        if !detail.is_empty() {
+                                            span { class: "combobox-detail", "{ detail }" }
+                                        }
+                                    }
+                                }
+                            },
+                            Entry::Create(key) => {
+                                let active = choices.get(*highlighted.read()) == Some(&entry);
+                                let mut pick = pick.clone();
+                                rsx! {
+                                    li {
+                                        class: if active { "combobox-option active" } else { "combobox-option" },
+                                        onmousedown: move |event| {
+                                            event.prevent_default();
+                                            pick(entry.clone());
+                                        },
+                                        "+ Add \"{ key }\" to { id }"
+                                    }
+                                }
+                            },
+                        }
+                    }
+                }
+            }
+            if !message.read().is_empty() {
+                p { class: "field-error", "{ message.read() }" }
+            }
+        }
+    }
+}
+
+
+/// Lists the dropdown entries for a query
+///
+/// Without a query, options are shown by category in table order. With one, they are ranked by
+/// [`fuzzy::rank`] and followed by a shortcut to add the query as a new option if no key equals it.
+fn entries(content: &[Vec<String>], query: &str, selected: &[String], multiple: bool) -> Vec<Entry> {
+    let available = |i: &usize| !(multiple && selected.contains(&content[*i][0]));
+    let option = |i: usize| Entry::Option {
+        key: content[i][0].clone(),
+        detail: content[i].iter().skip(1).filter(|cell| !cell.is_empty()).cloned().collect::<Vec<String>>().join(" · "),
+    };
+    let query = query.trim();
+
+    if query.is_empty() {
+        let mut result = Vec::new();
+        for (path, rows) in scroll_processor::group_rows(content) {
+            let rows: Vec<usize> = rows.into_iter().filter(available).collect();
+            if !path.is_empty() && !rows.is_empty() {
+                result.push(Entry::Heading(path));
+            }
+            result.extend(rows.into_iter().map(option));
+        }
+        result.truncate(MAX_OPTIONS);
+        return result;
+    }
+
+    let rows = content.get(1..).unwrap_or_default();
+    let mut result: Vec<Entry> = fuzzy::rank(query, rows).into_iter()
+        .map(|i| i + 1)
+        .filter(available)
+        .take(MAX_OPTIONS)
+        .map(option)
+        .collect();
+    if !rows.iter().any(|row| row[0] == query) {
+        result.push(Entry::Create(query.to_string()));
+    }
+    result
+}
@@ -1,16 +1,88 @@
-use crate::prelude::*;
+use crate::{
+    prelude::*,
+    tools::{
+        compare,
+        fuzzy,
+        integrity::{self, Usage},
+    },
+};
 
+/// The database shown in the popup, and the index of the form field that opened it, if any
 #[derive(Clone, Debug)]
-pub struct CurrentDB(pub Signal<String>);
+pub struct CurrentDB(pub Signal<String>, pub Signal<Option<usize>>);
 
 #[component]
 pub fn DBPopup() -> Element {
     let db_binding = use_context::<CurrentDB>().0;
+    let target = *use_context::<CurrentDB>().1.read();
     let db_name = db_binding.read().clone();
-    let db_content = scroll_processor::db_query(&db_name);
-    assert!(db_content.is_ok(), "{}", "ERR[0|1]: Database {db_name} is malformed.");
-    let content = db_content.unwrap().0;
-    let first = content.get(0).unwrap();
+
+    // Row being edited; the index one past the last row stands for a new row
+    let mut editing: Signal<Option<usize>> = use_signal(|| None);
+    let mut deleting: Signal<Option<usize>> = use_signal(|| None);
+    let mut draft: Signal<Vec<String>> = use_signal(Vec::new);
+    let mut message = use_signal(String::new);
+    // Entries using the key of the row being edited or deleted
+    let mut usages: Signal<Vec<Usage>> = use_signal(Vec::new);
+    // Directories whose entries could not be checked for the key
+    let mut skipped: Signal<Vec<String>> = use_signal(Vec::new);
+    let mut cascade = use_signal(|| true);
+
+    let mut search = use_signal(String::new);
+    let mut filters: Signal<Vec<String>> = use_signal(Vec::new);
+    // Column to sort by, and whether the order is ascending
+    let mut sorting: Signal<Option<(usize, bool)>> = use_signal(|| None);
+
+    // A different database starts with a clean view
+    use_effect(move || {
+        db_binding.read();
+        search.set(String::new());
+        filters.set(Vec::new());
+        sorting.set(None);
+        editing.set(None);
+        deleting.set(None);
+    });
+
+    // The table may have been removed or broken outside Scroll while the popup is mounted
+    let (content, first) = match scroll_processor::db_query(&db_name) {
+        Ok((content, _)) => {
+            let first = content.first().cloned().unwrap_or_default();
+            (content, first)
+        },
+        Err(e) => return rsx! {
+            dialog {
+                id: "db-popup",
+                class: "creator-popup",
+                div {
+                    class: "metadata-div",
+                    h1 { "Database: " u { "{db_name}" } }
+                    p { class: "field-error", "This database could not be opened. { e }" }
+                    button {
+                        class: "close-button",
+                        onclick: move |_| {
+                            document::eval(r#"
+const dialog = document.getElementById("db-popup");
+dialog.close();"#);
+                        },
+                        "Close"
+                    }
+                }
+            }
+        },
+    };
+    let width = first.len();
+    let new_index = content.len();
+    let groups = scroll_processor::group_rows(&content);
+    let categories: Vec<String> = groups.iter()
+        .map(|(path, _)| path.clone())
+        .filter(|path| !path.is_empty())
+        .collect();
+    let layout = match search.read().trim().is_empty() && filters.read().iter().all(|f| f.is_empty()) && sorting.read().is_none() {
+        true => groups,
+        false => vec![(String::new(), visible_rows(&content, &search.read(), &filters.read(), *sorting.read()))],
+    };
+    let target_field = target.and_then(|i| FILE_DATA.read().attributes.get(i).map(|(title, field, _)| (title.clone(), field.clone())));
+
     rsx! {
         dialog {
             id: "db-popup",
@@ -18,34 +90,287 @@ pub fn DBPopup() -> Element {
             div {
                 class: "metadata-div",
                 h1 { "Database: " u { "{db_name}" } }
+                if let Some((title, _)) = target_field.as_ref() {
+                    p { "Pick a row to use it for " b { "{ title }" } "." }
+                }
+                for warning in scroll_processor::warnings(&db_name) {
+                    p { class: "warning-msg", "⚠️ { warning }" }
+                }
+                input {
+                    class: "db-search",
+                    placeholder: "Search all columns...",
+                    value: "{ search.read() }",
+                    oninput: move |event| search.set(event.value()),
+                }
                 div {
                     class: "table-div",
                     table {
                         thead {
                             tr {
-                                for title in first.iter() {
+                                th { "" }
+                                for (column, title) in first.iter().enumerate() {
                                     th {
+                                        class: "sortable",
+                                        title: "Sort by { title }",
+                                        onclick: move |_| {
+                                            let next = match *sorting.read() {
+                                                Some((current, true)) if current == column => Some((column, false)),
+                                                Some((current, false)) if current == column => None,
+                                                _ => Some((column, true)),
+                                            };
+                                            sorting.set(next);
+                                        },
                                         "{title}"
+                                        match *sorting.read() {
+                                            Some((current, true)) if current == column => rsx! { " ▲" },
+                                            Some((current, false)) if current == column => rsx! { " ▼" },
+                                            _ => rsx! {},
+                                        }
+                                    }
+                                }
+                            }
+                            tr {
+                                class: "db-filters",
+                                th { "" }
+                                for column in 0..width {
+                                    th {
+                                        input {
+                                            placeholder: "Filter",
+                                            value: "{ filters.read().get(column).cloned().unwrap_or_default() }",
+                                            oninput: move |event| {
+                                                let mut filters = filters.write();
+                                                if filters.len() < width {
+                                                    filters.resize(width, String::new());
+                                                }
+                                                filters[column] = event.value();
+                                            }
+                                        }
                                     }
                                 }
                             }
                         }
                         tbody {
-                            for row in content.iter().skip(1) {
-                                tr {
-                                    for cell in row.iter() {
+                            for (path, indices) in layout.into_iter() {
+                                if !path.is_empty() {
+                                    tr {
+                                        class: "db-group",
+                                        td {
+                                            colspan: "{ width + 1 }",
+                                            style: "padding-left: { path.matches(\" > \").count() * 20 + 10 }px",
+                                            "{ path }"
+                                        }
+                                    }
+                                }
+                                for (i, row) in indices.into_iter().map(|i| (i, content[i].clone())) {
+                                    tr {
                                         td {
-                                            "{cell}"
+                                            class: "action-cell",
+                                            if let Some((_, field)) = target_field.clone() {
+                                                button {
+                                                    class: "action-button",
+                                                    title: "Use this value",
+                                                    onclick: {
+                                                        let key = row[0].clone();
+                                                        move |_| {
+                                                            use_value(target.unwrap(), &field, key.clone());
+                                                            document::eval(r#"
+const dialog = document.getElementById("db-popup");
+dialog.close();"#);
+                                                        }
+                                                    },
+                                                    "✓"
+                                                }
+                                            }
+                                            button {
+                                                class: "action-button",
+                                                title: "Edit this row",
+                                                onclick: {
+                                                    let row = row.clone();
+                                                    let db_name = db_name.clone();
+                                                    move |_| {
+                                                        let (found, unread) = integrity::usages(&db_name, &row[0]);
+                                                        usages.set(found);
+                                                        skipped.set(unread);
+                                                        cascade.set(true);
+                                                        draft.set(row.clone());
+                                                        deleting.set(None);
+                                                        editing.set(Some(i));
+                                                    }
+                                                },
+                                                "✎"
+                                            }
+                                            button {
+                                                class: "action-button",
+                                                title: "Delete this row",
+                                                onclick: {
+                                                    let key = row[0].clone();
+                                                    let db_name = db_name.clone();
+                                                    move |_| {
+                                                        let (found, unread) = integrity::usages(&db_name, &key);
+                                                        usages.set(found);
+                                                        skipped.set(unread);
+                                                        editing.set(None);
+                                                        deleting.set(Some(i));
+                                                    }
+                                                },
+                                                "🗑️"
+                                            }
                                         }
-                                    } 
+                                        for cell in row.iter() {
+                                            td {
+                                                "{cell}"
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                if let Some(i) = *deleting.read() {
+                    div {
+                        class: "db-editor",
+                        p { "Delete \"{ content.get(i).and_then(|row| row.first()).cloned().unwrap_or_default() }\" from { db_name }?" }
+                        if !usages.read().is_empty() {
+                            p {
+                                class: "warning-msg",
+                                "⚠️ Still used by { usages.read().len() } entries, which will keep a value that no longer exists:"
+                            }
+                            UsageList { usages: usages.read().clone() }
+                        }
+                        SkippedList { dirs: skipped.read().clone() }
+                        button {
+                            class: "close-button",
+                            onclick: {
+                                let db_name = db_name.clone();
+                                let content = content.clone();
+                                move |_| {
+                                    let mut rows = content.clone();
+                                    rows.remove(i);
+                                    match save_rows(&db_name, &rows, None) {
+                                        Ok(()) => {
+                                            deleting.set(None);
+                                            message.set(String::new());
+                                        },
+                                        Err(e) => message.set(e),
+                                    }
+                                }
+                            },
+                            "Delete"
+                        }
+                        button {
+                            onclick: move |_| deleting.set(None),
+                            "Keep"
+                        }
+                    }
+                }
+                if let Some(i) = *editing.read() {
+                    div {
+                        class: "db-editor",
+                        h2 {
+                            if i == new_index { "New row" } else { "Editing row { i }" }
+                        }
+                        for (column, title) in first.iter().enumerate() {
+                            div {
+                                class: "db-editor-field",
+                                label {
+                                    if column == 0 { "{ title }* (must be unique)" } else { "{ title }" }
+                                }
+                                input {
+                                    list: if title.eq_ignore_ascii_case("category") { "db-categories" } else { "" },
+                                    placeholder: if title.eq_ignore_ascii_case("category") { "e.g. Enzymes > Restriction" } else { "" },
+                                    value: "{ draft.read().get(column).cloned().unwrap_or_default() }",
+                                    oninput: move |event| {
+                                        if let Some(cell) = draft.write().get_mut(column) {
+                                            *cell = event.value();
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        if i != new_index && !usages.read().is_empty() {
+                            if content[i][0] != draft.read()[0].trim() {
+                                label {
+                                    input {
+                                        type: "checkbox",
+                                        checked: *cascade.read(),
+                                        oninput: move |event| cascade.set(event.checked()),
+                                    }
+                                    " Also rename \"{ content[i][0] }\" in the { usages.read().len() } entries that use it"
                                 }
+                                UsageList { usages: usages.read().clone() }
+                            } else {
+                                p { "Used by { usages.read().len() } entries." }
                             }
                         }
+                        if i != new_index {
+                            SkippedList { dirs: skipped.read().clone() }
+                        }
+                        datalist {
+                            id: "db-categories",
+                            for category in categories.iter() {
+                                option { value: "{ category }" }
+                            }
+                        }
+                        button {
+                            class: "close-button",
+                            onclick: move |_| editing.set(None),
+                            "Cancel"
+                        }
+                        button {
+                            class: "creation-button",
+                            onclick: {
+                                let db_name = db_name.clone();
+                                let content = content.clone();
+                                move |_| {
+                                    let row: Vec<String> = draft.read().iter().map(|cell| cell.trim().to_string()).collect();
+                                    let rename = content.get(i)
+                                        .filter(|old| *cascade.read() && old[0] != row[0] && !usages.read().is_empty())
+                                        .map(|old| (old[0].clone(), row[0].clone()));
+                                    let result = check_row(&content, &row, i).and_then(|_| {
+                                        let mut rows = content.clone();
+                                        match i == rows.len() {
+                                            true => rows.push(row),
+                                            false => rows[i] = row,
+                                        }
+                                        save_rows(&db_name, &rows, rename)
+                                    });
+                                    match result {
+                                        Ok(()) => {
+                                            editing.set(None);
+                                            message.set(String::new());
+                                        },
+                                        Err(e) => message.set(e),
+                                    }
+                                }
+                            },
+                            "Save"
+                        }
                     }
                 }
+                p { class: "warning-msg", "{ message.read() }" }
+                button {
+                    onclick: move |_| {
+                        draft.set(vec![String::new(); width]);
+                        deleting.set(None);
+                        editing.set(Some(new_index));
+                    },
+                    "Add row"
+                }
                 button {
                     onclick: move |_| {
                         document::eval(r#"
+const dialog = document.getElementById("importer");
+dialog.showModal();"#);
+                    },
+                    "Import from spreadsheet..."
+                }
+                button {
+                    onclick: move |_| {
+                        editing.set(None);
+                        deleting.set(None);
+                        message.set(String::new());
+                        document::eval(r#"
 const dialog = document.getElementById("db-popup");
 dialog.close();"#);
                     },
@@ -57,13 +382,147 @@ dialog.close();"#);
 }
 
 
+/// Orders the rows matching the search and column filters
+///
+/// # Props
+/// - `content`: The table, header first
+/// - `search`: Text matched fuzzily against every column
+/// - `filters`: Text each column has to contain, ignoring case
+/// - `sorting`: The column to sort by and whether the order is ascending; search relevance is used otherwise
+///
+/// # Returns
+/// The indices of the visible rows in `content`
+fn visible_rows(content: &[Vec<String>], search: &str, filters: &[String], sorting: Option<(usize, bool)>) -> Vec<usize> {
+    let rows = content.get(1..).unwrap_or_default();
+    let mut visible: Vec<usize> = match search.trim().is_empty() {
+        true => (0..rows.len()).collect(),
+        false => fuzzy::rank(search, rows),
+    };
+    visible.retain(|i| filters.iter()
+        .enumerate()
+        .all(|(column, filter)| rows[*i].get(column)
+            .is_some_and(|cell| cell.to_lowercase().contains(&filter.trim().to_lowercase()))));
+    if let Some((column, ascending)) = sorting {
+        visible.sort_by(|a, b| {
+            let order = compare::natural(&rows[*a][column], &rows[*b][column]);
+            if ascending { order } else { order.reverse() }
+        });
+    }
+    visible.into_iter().map(|i| i + 1).collect()
+}
+
+
+/// Fills the form field that opened the popup with a row's key
+///
+/// `Multi` fields gain the key as another selection; other fields are replaced.
+fn use_value(index: usize, field: &InputField, key: String) {
+    let generator = &mut POPUP_GENERATOR.write();
+    let value = match generator.metadata.get_mut(index) {
+        Some(v) => v,
+        None => return,
+    };
+    *value = match field.is_list() {
+        true => {
+            let mut selections = value.items();
+            if !selections.contains(&key) {
+                selections.push(key);
+            }
+            FieldValue::List(selections)
+        },
+        false => FieldValue::Text(key),
+    };
+}
+
+
+/// Checks an edited row before it is written
+///
+/// # Props
+/// - `content`: The table as stored, header first
+/// - `row`: The edited row
+/// - `index`: The position of the row in `content`, or `content.len()` for a new row
+///
+/// # Returns
+/// - `Ok` if the row has a key that no other row uses
+/// - `Err(e)` describing the problem to the user
+fn check_row(content: &[Vec<String>], row: &[String], index: usize) -> Result<(), String> {
+    let key = row.first().cloned().unwrap_or_default();
+    if key.is_empty() {
+        return Err("The first column identifies the row and cannot be empty.".to_string());
+    }
+    let duplicate = content.iter()
+        .enumerate()
+        .skip(1)
+        .any(|(i, other)| i != index && other.first() == Some(&key));
+    match duplicate {
+        true => Err(format!("\"{key}\" is already in this database.")),
+        false => Ok(()),
+    }
+}
+
+
+/// Writes a table and refreshes the directory, so `One` and `Multi` selects offer the new options
+///
+/// A renamed key is looked up in every database first, so nothing is written if one cannot be read.
+///
+/// # Props
+/// - `db_name`: The name of the database in `DOC_DIR/sys`
+/// - `rows`: Every row of the table, header first
+/// - `rename`: A changed key to replace in every entry of the workspace, as `(old, new)`
+fn save_rows(db_name: &str, rows: &[Vec<String>], rename: Option<(String, String)>) -> Result<(), String> {
+    let rewrites = match rename {
+        Some((old, new)) => integrity::plan_rename(db_name, &old, &new).map_err(|e| e.to_string())?,
+        None => Vec::new(),
+    };
+    scroll_processor::write_db(db_name, rows).map_err(|e| e.to_string())?;
+    let renamed = integrity::apply_rename(&rewrites)
+        .map_err(|e| format!("The table was saved, but not every entry could be updated. {e}"));
+    FILE_DATA.write().refresh()?;
+    renamed
+}
+
+
+#[component]
+fn UsageList(usages: Vec<Usage>) -> Element {
+    rsx! {
+        ul {
+            class: "usage-list",
+            for usage in usages.iter() {
+                li { "{ usage.dir }/{ deserialize(&usage.entry) } ({ usage.attribute })" }
+            }
+        }
+    }
+}
+
+
+/// Directories left out of a usage count because their attributes or records could not be read
+#[component]
+fn SkippedList(dirs: Vec<String>) -> Element {
+    rsx! {
+        if !dirs.is_empty() {
+            p {
+                class: "warning-msg",
+                "⚠️ Entries in { dirs.join(\", \") } could not be checked, as their attributes or records could not be read."
+            }
+        }
+    }
+}
+
+
 
+/// Button opening the popup for a sys database
+///
+/// # Props
+/// - `id`: The name of the database
+/// - `target`: The index of the form field to fill when a row is picked, if opened from the file creator
 #[component]
-pub fn PopupOpener(id: String) -> Element {
+pub fn PopupOpener(id: String, target: Option<usize>) -> Element {
     rsx! {
         button {
-            onclick: move |_| {
+            onclick: move |event| {
+                // Keeps a click inside a table header from also sorting the column
+                event.stop_propagation();
                 *use_context::<CurrentDB>().0.write() = id.clone();
+                *use_context::<CurrentDB>().1.write() = target;
                 document::eval(r#"
 const dialog = document.getElementById("db-popup");
 dialog.showModal();"#);
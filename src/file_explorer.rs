@@ -3,11 +3,22 @@ use tokio::process::Command;
 use crate::{
     metadata_popup::Creator,
     db_popup::*,
+    migration_popup::{MigrationBanner, Migrator},
+    import_popup::Importer,
+    search_popup::{use_search_index, SearchButton, SearchPopup},
     home::Home,
+    watcher::use_workspace_watcher,
+    types::files::SortDirection,
+    tools::{compare::EmptyPlacement, query::ColumnFilter},
 };
 
 
 
+/// Rows of the file table under one group header, given as its label and row count when grouping
+type Section = (Option<(String, usize)>, Vec<usize>);
+
+
+
 async fn marktext(filename: String) {
     Command::new("/apps/marktext")
         .arg(filename)
@@ -17,6 +28,37 @@ async fn marktext(filename: String) {
 }
 
 
+/// Opens a link with the default application of the platform
+///
+/// # Returns
+/// - `Ok` if the opener ran and succeeded
+/// - `Err(e)` if it is missing or reported a failure
+async fn browser(url: String) -> Result<(), String> {
+    let mut command = match std::env::consts::OS {
+        "macos" => Command::new("open"),
+        // `start` would need `cmd`, which splits URLs on `&`
+        "windows" => {
+            let mut command = Command::new("rundll32");
+            command.arg("url.dll,FileProtocolHandler");
+            command
+        },
+        _ => Command::new("xdg-open"),
+    };
+    let output = command.arg(&url)
+        .output()
+        .await
+        .map_err(|e| format!("No application could be started to open {url}. {e}"))?;
+    match output.status.success() {
+        true => Ok(()),
+        false => Err(format!(
+            "{url} could not be opened ({}). {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        )),
+    }
+}
+
+
 fn Breadcrumbs() -> Element {
     let breadcrumbs = FILE_DATA.read().breadcrumbs.clone();
     rsx! {
@@ -36,6 +78,8 @@ fn Breadcrumbs() -> Element {
 }
     span {
         class: "new-button",
+        ViewPicker {}
+        SearchButton {}
         NewButton {}
 }
 }
@@ -43,6 +87,86 @@ fn Breadcrumbs() -> Element {
 }
 
 
+/// Dropdown of the saved views of the directory, with controls to save and delete them
+fn ViewPicker() -> Element {
+    let mut naming = use_signal(|| false);
+    let mut name = use_signal(String::new);
+    let mut message = use_signal(String::new);
+    let views: Vec<String> = FILE_DATA.read().views.iter().map(|view| view.name.clone()).collect();
+    let active = FILE_DATA.read().active_view.clone();
+    let views_error = FILE_DATA.read().views_error.clone();
+    if FILE_DATA.read().attributes.is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        span {
+            class: "view-picker",
+            select {
+                title: "Saved views of this directory",
+                oninput: move |event| {
+                    let value = event.value();
+                    FILE_DATA.write().apply_view(match value.is_empty() {
+                        true => None,
+                        false => Some(&value),
+                    });
+                },
+                option { value: "", selected: active.is_none(), "All entries" }
+                for view in views.iter() {
+                    option { value: "{ view }", selected: active.as_ref() == Some(view), "{ view }" }
+                }
+            }
+            if let Some(e) = views_error {
+                span { class: "field-error", "{ e }" }
+            } else if *naming.read() {
+                input {
+                    placeholder: "View name",
+                    value: "{ name.read() }",
+                    oninput: move |event| name.set(event.value()),
+                }
+                button {
+                    onclick: move |_| {
+                        let result = FILE_DATA.write().save_view(&name.read());
+                        match result {
+                            Ok(()) => {
+                                naming.set(false);
+                                message.set(String::new());
+                            },
+                            Err(e) => message.set(e),
+                        }
+                    },
+                    "Save"
+                }
+                button { onclick: move |_| naming.set(false), "Cancel" }
+            } else {
+                button {
+                    title: "Save the current filters, sort, columns and grouping as a view",
+                    onclick: move |_| {
+                        name.set(FILE_DATA.read().active_view.clone().unwrap_or_default());
+                        naming.set(true);
+                    },
+                    "Save view"
+                }
+                if let Some(view) = active.clone() {
+                    button {
+                        title: "Delete the view \"{ view }\" for everyone",
+                        onclick: move |_| {
+                            if let Err(e) = FILE_DATA.write().delete_view(&view) {
+                                message.set(e);
+                            }
+                        },
+                        "Delete view"
+                    }
+                }
+            }
+            if !message.read().is_empty() {
+                span { class: "field-error", "{ message.read() }" }
+            }
+        }
+    }
+}
+
+
 fn NewButton() -> Element {
     let attributes = &FILE_DATA.read().attributes;
     let metadata = &FILE_DATA.read().metadata;
@@ -85,39 +209,385 @@ fn Directories() -> Element {
 }
 
 
+fn SchemaErrorPanel() -> Element {
+    let error = FILE_DATA.read().schema_error.clone();
+    match error {
+        None => rsx! {},
+        Some(e) => rsx! {
+            div {
+                class: "schema-error",
+                h3 { "⚠️ This directory's attributes could not be read" }
+                p { "{ e.file.display() } (line { e.line }, column { e.column })" }
+                pre { "{ e.snippet }" }
+                p { "{ e.message }" }
+            }
+        },
+    }
+}
+
+
+/// Changes made outside Scroll that could not be loaded, e.g. a table broken by a merge
+fn WatcherErrors() -> Element {
+    let mut errors: Vec<(String, String)> = WATCHER_ERRORS.read().iter()
+        .map(|(source, e)| (source.clone(), e.clone()))
+        .collect();
+    errors.sort();
+    rsx! {
+        if !errors.is_empty() {
+            div {
+                class: "schema-error",
+                h3 { "⚠️ Some changes to the workspace could not be loaded" }
+                for (source, e) in errors {
+                    p { b { "{ source }: " } "{ e }" }
+                }
+            }
+        }
+    }
+}
+
+
+/// Rows of the tables in `DOC_DIR/sys` that were left out while reading them
+fn DatabaseWarnings() -> Element {
+    // The watcher reloads tables before refreshing the open directory, so re-render along with it
+    let _ = FILE_DATA.read();
+    let mut warnings: Vec<String> = DATABASE_WARNINGS.read().unwrap().values().flatten().cloned().collect();
+    warnings.sort();
+    rsx! {
+        if !warnings.is_empty() {
+            div {
+                class: "schema-error",
+                h3 { "⚠️ Some database rows were ignored" }
+                for warning in warnings {
+                    p { "{ warning }" }
+                }
+            }
+        }
+    }
+}
+
+
+/// Arrow showing the sort direction of a column, numbered when several sort keys are active
+#[component]
+fn SortIndicator(column: String) -> Element {
+    let ordering = FILE_DATA.read().ordering.clone();
+    match ordering.key_of(&column) {
+        None => rsx! {},
+        Some((position, direction)) => {
+            let arrow = match direction {
+                SortDirection::Increasing => "▲",
+                SortDirection::Decreasing => "▼",
+            };
+            rsx! {
+                span {
+                    class: "sort-indicator",
+                    "{ arrow }"
+                    if ordering.keys.len() > 1 {
+                        sub { "{ position + 1 }" }
+                    }
+                }
+            }
+        },
+    }
+}
+
+
+/// Row of filter inputs under the table headers, chosen by attribute type
+///
+/// Text columns filter by substring, numbers and dates by an inclusive range, and options by a picker.
+fn FilterRow() -> Element {
+    let attributes = FILE_DATA.read().attributes.clone();
+    let hidden = FILE_DATA.read().hidden_columns.clone();
+    let filters = FILE_DATA.read().column_filters.clone();
+    let text_of = |column: &str| match filters.get(column) {
+        Some(ColumnFilter::Contains(text)) => text.clone(),
+        _ => String::new(),
+    };
+    let range_of = |column: &str| match filters.get(column) {
+        Some(ColumnFilter::Range { from, to }) => (from.clone(), to.clone()),
+        _ => (String::new(), String::new()),
+    };
+    let option_of = |column: &str| match filters.get(column) {
+        Some(ColumnFilter::Options(options)) => options.first().cloned().unwrap_or_default(),
+        _ => String::new(),
+    };
+
+    rsx! {
+        tr {
+            class: "table-filters",
+            th {
+                input {
+                    placeholder: "Filter",
+                    value: "{ text_of(\"__ID\") }",
+                    oninput: move |event| FILE_DATA.write().set_column_filter("__ID", ColumnFilter::Contains(event.value())),
+                }
+            }
+            th { "" }
+            for (title, field, _) in attributes.into_iter().filter(|(title, _, _)| !hidden.contains(title)) {
+                th {
+                    match field {
+                        InputField::Number { .. } | InputField::Date { .. } | InputField::DateTime { .. }
+                        | InputField::Created | InputField::Modified | InputField::Duration { .. } => {
+                            let kind = match field {
+                                InputField::Number { .. } => "number",
+                                InputField::Duration { .. } => "text",
+                                _ => "date",
+                            };
+                            let (from, to) = range_of(&title);
+                            let (from_title, to_title) = (title.clone(), title.clone());
+                            let (current_to, current_from) = (to.clone(), from.clone());
+                            rsx! {
+                                div {
+                                    class: "range-filter",
+                                    input {
+                                        r#type: kind,
+                                        placeholder: "From",
+                                        value: "{ from }",
+                                        oninput: move |event| FILE_DATA.write().set_column_filter(&from_title, ColumnFilter::Range {
+                                            from: event.value(),
+                                            to: current_to.clone(),
+                                        }),
+                                    }
+                                    input {
+                                        r#type: kind,
+                                        placeholder: "To",
+                                        value: "{ to }",
+                                        oninput: move |event| FILE_DATA.write().set_column_filter(&to_title, ColumnFilter::Range {
+                                            from: current_from.clone(),
+                                            to: event.value(),
+                                        }),
+                                    }
+                                }
+                            }
+                        },
+                        InputField::One { .. } | InputField::Multi { .. } | InputField::Choice { .. }
+                        | InputField::Choices { .. } | InputField::Bool { .. } => {
+                            let options: Vec<(String, String)> = match &field {
+                                InputField::One { id, .. } | InputField::Multi { id, .. } => {
+                                    scroll_processor::db_query(id).map(|v| v.0).unwrap_or_default().into_iter()
+                                        .skip(1)
+                                        .filter_map(|row| row.first().cloned())
+                                        .map(|key| (key.clone(), key))
+                                        .collect()
+                                },
+                                InputField::Choice { options, .. } | InputField::Choices { options, .. } => {
+                                    options.iter().map(|option| (option.clone(), option.clone())).collect()
+                                },
+                                _ => vec![("true".to_string(), "Yes".to_string()), ("false".to_string(), "No".to_string())],
+                            };
+                            let selected = option_of(&title);
+                            rsx! {
+                                select {
+                                    oninput: move |event| {
+                                        let options = match event.value().is_empty() {
+                                            true => Vec::new(),
+                                            false => vec![event.value()],
+                                        };
+                                        FILE_DATA.write().set_column_filter(&title, ColumnFilter::Options(options));
+                                    },
+                                    option { value: "", selected: selected.is_empty(), "Any" }
+                                    for (value, label) in options.into_iter() {
+                                        option { value: "{ value }", selected: value == selected, "{ label }" }
+                                    }
+                                }
+                            }
+                        },
+                        _ => {
+                            let text = text_of(&title);
+                            rsx! {
+                                input {
+                                    placeholder: "Filter",
+                                    value: "{ text }",
+                                    oninput: move |event| FILE_DATA.write().set_column_filter(&title, ColumnFilter::Contains(event.value())),
+                                }
+                            }
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
+
+
 fn FileTable() -> Element {
     let attributes = &FILE_DATA.read().attributes;
     let metadata = FILE_DATA.read().metadata.clone();
+    let visible = FILE_DATA.read().visible.clone();
+    let hidden = FILE_DATA.read().hidden_columns.clone();
+    let group_by = FILE_DATA.read().group_by.clone();
+    // Labels of the groups folded away
+    let mut collapsed: Signal<Vec<String>> = use_signal(Vec::new);
+    let sections: Vec<Section> = match group_by.is_some() {
+        true => FILE_DATA.read().groups().into_iter()
+            .map(|(label, rows)| (Some((label, rows.len())), rows))
+            .collect(),
+        false => vec![(None, visible.clone())],
+    };
+    let group_title = group_by.clone().unwrap_or_default();
+    let width = 2 + FILE_DATA.read().attributes.iter().filter(|(title, _, _)| !hidden.contains(title)).count();
+    let groupable: Vec<String> = FILE_DATA.read().attributes.iter()
+        .filter(|(_, field, _)| matches!(field, InputField::One { .. } | InputField::Choice { .. }))
+        .map(|(title, _, _)| title.clone())
+        .collect();
+    let query = FILE_DATA.read().query.clone();
+    let query_error = FILE_DATA.read().query_error.clone();
+    let empties = FILE_DATA.read().ordering.empties;
+    let preference_error = FILE_DATA.read().preference_error.clone();
+    // Entries of every referenced directory, queried once for dangling reference detection
+    let references: HashMap<String, Vec<String>> = attributes.iter()
+        .filter_map(|(_, attribute_type, _)| match attribute_type {
+            InputField::Ref { dir, .. } | InputField::Refs { dir, .. } => {
+                Some((dir.clone(), scroll_processor::ref_query(dir).unwrap_or_default()))
+            },
+            _ => None,
+        })
+        .collect();
     if attributes.is_empty() {
         return rsx! {};
     } else {
         rsx! {
+            div {
+                class: "table-options",
+                input {
+                    class: "query-bar",
+                    placeholder: "Filter, e.g. Owner = \"Ana\" AND Date >= 2025-06-01",
+                    value: "{ query }",
+                    oninput: move |event| FILE_DATA.write().set_query(event.value()),
+                }
+                span { "{ visible.len() } of { metadata.len() }" }
+                if !groupable.is_empty() {
+                    "Group by "
+                    select {
+                        oninput: move |event| {
+                            let value = event.value();
+                            FILE_DATA.write().set_group_by(match value.is_empty() {
+                                true => None,
+                                false => Some(value),
+                            });
+                            collapsed.write().clear();
+                        },
+                        option { value: "", selected: group_by.is_none(), "Nothing" }
+                        for title in groupable.iter() {
+                            option { value: "{ title }", selected: group_by.as_ref() == Some(title), "{ title }" }
+                        }
+                    }
+                }
+                details {
+                    class: "column-picker",
+                    summary { "Columns" }
+                    for (title, _, _) in attributes.iter() {
+                        label {
+                            input {
+                                r#type: "checkbox",
+                                checked: !hidden.contains(title),
+                                oninput: {
+                                    let title = title.clone();
+                                    move |_| FILE_DATA.write().toggle_column(&title)
+                                },
+                            }
+                            " { title }"
+                        }
+                    }
+                }
+                "Empty values "
+                select {
+                    oninput: move |event| FILE_DATA.write().set_empty_placement(match event.value().as_str() {
+                        "first" => EmptyPlacement::First,
+                        _ => EmptyPlacement::Last,
+                    }),
+                    option { value: "last", selected: empties == EmptyPlacement::Last, "last" }
+                    option { value: "first", selected: empties == EmptyPlacement::First, "first" }
+                }
+            }
+            if let Some(e) = query_error {
+                p { class: "field-error", "{ e }" }
+            }
+            if let Some(e) = preference_error {
+                p { class: "field-error", "{ e }" }
+            }
             div {
                 class: "table-div",
                 table {
                 thead {
                 tr {
-                th { "" }
+                th {
+                    class: "sortable",
+                    title: "Click to sort, shift-click to add a secondary sort key",
+                    onclick: move |event: MouseEvent| FILE_DATA.write().sort_by("__ID", event.modifiers().shift()),
+                    "Name"
+                    SortIndicator { column: "__ID" }
+                }
             th { "" }
-        for (attribute_name, attribute_type) in attributes.iter() {
+        for (attribute_name, attribute_type, attribute_info) in attributes.iter().filter(|(title, _, _)| !hidden.contains(title)) {
             th {
+            class: "sortable",
+            title: attribute_info.help.clone().unwrap_or_default(),
+            onclick: {
+                let column = attribute_name.clone();
+                move |event: MouseEvent| FILE_DATA.write().sort_by(&column, event.modifiers().shift())
+            },
             "{attribute_name}"
+            SortIndicator { column: attribute_name.clone() }
+            if let Some(origin) = attribute_info.origin.label() {
+                span { class: "attribute-origin", "{ origin }" }
+            }
             match attribute_type {
                 InputField::String { .. } => rsx! {},
+                InputField::Number { .. } => rsx! {},
+                InputField::Bool { .. } => rsx! {},
                 InputField::Date { .. }=> rsx! {},
+                InputField::DateTime { .. } => rsx! {},
+                InputField::Duration { .. } => rsx! {},
                 InputField::One { id, .. } => rsx! {
                     PopupOpener { id: id }
                 },
                 InputField::Multi { id, .. } => rsx! {
                     PopupOpener { id: id }
-                }
+                },
+                InputField::Choice { .. } => rsx! {},
+                InputField::Choices { .. } => rsx! {},
+                InputField::Url { .. } => rsx! {},
+                InputField::Doi { .. } => rsx! {},
+                InputField::Ref { .. } => rsx! {},
+                InputField::Refs { .. } => rsx! {},
+                InputField::Created => rsx! {},
+                InputField::Modified => rsx! {},
+                InputField::Author => rsx! {},
+                InputField::Seq { .. } => rsx! {},
             }
         }
     }
     }
+        FilterRow {}
     }
         tbody {
-            for (i, data) in metadata.into_iter().enumerate() {
+            for (group, rows) in sections.into_iter() {
+            if let Some((label, count)) = group.clone() {
+                tr {
+                    class: "group-header",
+                    onclick: {
+                        let label = label.clone();
+                        move |_| {
+                            let mut collapsed = collapsed.write();
+                            match collapsed.iter().position(|other| *other == label) {
+                                Some(i) => {
+                                    collapsed.remove(i);
+                                },
+                                None => collapsed.push(label.clone()),
+                            }
+                        }
+                    },
+                    td {
+                        colspan: "{ width }",
+                        if collapsed.read().contains(&label) { "▸ " } else { "▾ " }
+                        b { if label.is_empty() { "No { group_title }" } else { "{ label }" } }
+                        span { class: "group-count", " ({ count })" }
+                    }
+                }
+            }
+            for (i, data) in rows.into_iter()
+                .filter(|_| group.as_ref().is_none_or(|(label, _)| !collapsed.read().contains(label)))
+                .map(|i| (i, metadata[i].clone())) {
         tr {
         td {
         class: "marktext-cell",
@@ -131,7 +601,7 @@ fn FileTable() -> Element {
                 path.push(data
                     .get(0)
                     .unwrap()
-                    .clone());
+                    .to_string());
                 path.set_extension("md");
                 path
             };
@@ -139,7 +609,7 @@ fn FileTable() -> Element {
                 marktext(filepath.to_string_lossy().into_owned()).await;  
             });
         },
-        "{ deserialize(&data.get(0).unwrap()) }"
+        "{ deserialize(&data.get(0).unwrap().to_string()) }"
     }
     }
         td {
@@ -156,7 +626,7 @@ fn FileTable() -> Element {
 
                     assert!(title_binding.is_some(), "{}",
                         "ERR[1|1]: Metadata item {i} is empty.");
-                    title_binding.unwrap().clone()
+                    title_binding.unwrap().to_string()
                 };
                 set_editor_environment(name);
                 document::eval(r#"
@@ -166,17 +636,33 @@ dialog.showModal();"#);
             "⚙"
         }
     }
-        for data_out in data.iter().skip(1) {
-            if data_out.len() >= 30 {
+        for (data_out, (_, attribute_type, _)) in data.iter().skip(1).zip(attributes.iter()).filter(|(_, (title, _, _))| !hidden.contains(title)) {
+            if matches!(attribute_type, InputField::Url { .. } | InputField::Doi { .. }) && !data_out.is_empty() {
                 td {
                     class: "table-content",
-                    title: "{data_out.clone()}",
-                    "{data_out}"
+                    LinkCell { url: data_out.to_string() }
+                }
+            } else if let InputField::Ref { dir, .. } | InputField::Refs { dir, .. } = attribute_type {
+                td {
+                    class: "table-content",
+                    for reference in data_out.items() {
+                        RefCell {
+                            dir: dir.clone(),
+                            dangling: !references.get(dir).is_some_and(|ids| ids.contains(&reference)),
+                            id: reference,
+                        }
+                    }
+                }
+            } else if attribute_type.display(data_out).len() >= 30 {
+                td {
+                    class: "table-content",
+                    title: "{ data_out }",
+                    "{ attribute_type.display(data_out) }"
                 }
             } else {
                 td {
                 class: "table-content",
-                "{data_out}"
+                "{ attribute_type.display(data_out) }"
             }
         }
     }
@@ -187,15 +673,71 @@ dialog.showModal();"#);
     }
     }
     }
+    }
+}
+
+
+#[component]
+fn LinkCell(url: String) -> Element {
+    let target = url.clone();
+    let mut message = use_signal(String::new);
+    rsx! {
+        button {
+            class: "link-button",
+            title: "Open \"{ url }\" in browser",
+            onclick: move |_| {
+                let target = target.clone();
+                spawn(async move {
+                    match browser(target).await {
+                        Ok(()) => message.set(String::new()),
+                        Err(e) => message.set(e),
+                    }
+                });
+            },
+            "{ url }"
+        }
+        if !message.read().is_empty() {
+            span { class: "field-error", "{ message.read() }" }
+        }
+    }
+}
+
+
+#[component]
+fn RefCell(dir: String, id: String, dangling: bool) -> Element {
+    let target = DOC_DIR.read().unwrap().join(&dir);
+
+    if dangling {
+        rsx! {
+            span {
+                class: "dangling-ref",
+                title: "\"{ id }\" no longer exists in { dir }",
+                "⚠ { deserialize(&id) }"
+            }
+        }
+    } else {
+        rsx! {
+            button {
+                class: "link-button",
+                title: "Go to { dir }",
+                onclick: move |_| {
+                    FILE_DATA.write().goto(&target);
+                    POPUP_GENERATOR.write().refresh();
+                },
+                "{ deserialize(&id) }"
+            }
+        }
+    }
 }
 
+
 fn set_editor_environment(name: String) {
     let metadata = &FILE_DATA.read().metadata;
     let metadata_vec = metadata.par_iter()
         .find_any(|inner_vec| 
             inner_vec
                 .get(0)
-                .map(|v| *v == name)
+                .map(|v| v.to_string() == name)
                 .unwrap_or(false)
         );
 
@@ -211,10 +753,16 @@ fn set_editor_environment(name: String) {
 
 #[component]
 pub fn Viewer() -> Element {
-    use_context_provider(|| CurrentDB(Signal::new("Members".to_string())));
+    use_context_provider(|| CurrentDB(Signal::new("Members".to_string()), Signal::new(None)));
+    use_workspace_watcher();
+    use_search_index();
 
     if FILE_DATA.read().current_path == *DOC_DIR.read().unwrap() {
-        rsx! { Home {} }
+        rsx! {
+            WatcherErrors {}
+            Home {}
+            SearchPopup {}
+        }
     } else {
         rsx! {
             div {
@@ -222,10 +770,17 @@ pub fn Viewer() -> Element {
             br {}
             Directories {}
             br {}
+            WatcherErrors {}
+            SchemaErrorPanel {}
+            DatabaseWarnings {}
+            MigrationBanner {}
             FileTable {}
             br {}
             Creator {}
             DBPopup {}
+            Migrator {}
+            Importer {}
+            SearchPopup {}
         }
     }
     }
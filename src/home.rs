@@ -1,5 +1,8 @@
 #![allow(non_snake_case)]
-use crate::prelude::*;
+use crate::{
+    prelude::*,
+    search_popup::SearchButton,
+};
 
 
 pub fn Home() -> Element {
@@ -20,7 +23,8 @@ pub fn Home() -> Element {
                 button { onclick: move |_| {
                     FILE_DATA.write().goto(&drylab_path);
                 }, "Dry Lab" }
-                
+
+                SearchButton {}
             }
         }
     }
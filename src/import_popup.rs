@@ -0,0 +1,288 @@
+use crate::{
+    prelude::*,
+    db_popup::CurrentDB,
+    tools::spreadsheet::{self, ImportPlan, Sheet},
+};
+use native_dialog::FileDialog;
+
+
+
+static NEW_DATABASE: &str = "__NEW";
+/// Rows shown in the preview; the summary counts every row
+const PREVIEW_ROWS: usize = 20;
+
+
+
+/// Wizard for importing a spreadsheet into a sys database
+///
+/// The steps are shown top to bottom: pick a file and sheet, pick the database, map its columns,
+/// choose what happens to keys that already exist, then check the preview and import.
+#[component]
+pub fn Importer() -> Element {
+    let current_db = use_context::<CurrentDB>().0.read().clone();
+
+    let mut path = use_signal(String::new);
+    let mut sheets: Signal<Vec<Sheet>> = use_signal(Vec::new);
+    let mut sheet_index = use_signal(|| 0usize);
+    let mut has_header = use_signal(|| true);
+    // `None` follows the database the popup was opened from
+    let mut target: Signal<Option<String>> = use_signal(|| None);
+    let mut new_name = use_signal(String::new);
+    // Column mappings changed by hand, by database column
+    let mut overrides: Signal<HashMap<usize, Option<usize>>> = use_signal(HashMap::new);
+    let mut overwrite = use_signal(|| false);
+    let mut message = use_signal(String::new);
+
+    let mut databases: Vec<String> = DATABASE_HOLD.read().unwrap().keys().cloned().collect();
+    databases.sort();
+    let target_name = target.read().clone().unwrap_or(current_db);
+    let creating = target_name == NEW_DATABASE;
+
+    let sheet = sheets.read().get(*sheet_index.read()).cloned();
+    let (source_header, imported): (Vec<String>, Vec<Vec<String>>) = match &sheet {
+        None => (Vec::new(), Vec::new()),
+        Some(sheet) if *has_header.read() => (
+            sheet.rows.first().cloned().unwrap_or_default(),
+            sheet.rows.iter().skip(1).cloned().collect(),
+        ),
+        Some(sheet) => (
+            (1..=sheet.rows.first().map_or(0, |row| row.len())).map(|i| format!("Column {i}")).collect(),
+            sheet.rows.clone(),
+        ),
+    };
+
+    let existing: Vec<Vec<String>> = match creating {
+        true => vec![source_header.clone()],
+        false => scroll_processor::db_query(&target_name).map(|v| v.0).unwrap_or_default(),
+    };
+    let header = existing.first().cloned().unwrap_or_default();
+    let mapping: Vec<Option<usize>> = match creating {
+        true => (0..header.len()).map(Some).collect(),
+        false => spreadsheet::match_columns(&header, &source_header).into_iter()
+            .enumerate()
+            .map(|(column, guess)| overrides.read().get(&column).cloned().unwrap_or(guess))
+            .collect(),
+    };
+    let plan: Option<ImportPlan> = match sheet.is_some() && !header.is_empty() {
+        true => Some(spreadsheet::plan(&existing, &imported, &mapping, *overwrite.read())),
+        false => None,
+    };
+
+    let mut load = move |file: String| {
+        match spreadsheet::read_sheets(&PathBuf::from(&file)) {
+            Ok(v) => {
+                sheets.set(v);
+                message.set(String::new());
+            },
+            Err(e) => {
+                sheets.set(Vec::new());
+                message.set(e.to_string());
+            },
+        }
+        path.set(file);
+        sheet_index.set(0);
+        overrides.write().clear();
+    };
+
+    rsx! {
+        dialog {
+            id: "importer",
+            class: "creator-popup",
+            div {
+                class: "metadata-div",
+                h1 { "Import spreadsheet" }
+
+                h2 { "File" }
+                div {
+                    class: "migration-row",
+                    input {
+                        placeholder: "Path to an .xlsx, .ods or .csv file",
+                        value: "{ path.read() }",
+                        oninput: move |event| path.set(event.value()),
+                    }
+                    button {
+                        onclick: move |_| {
+                            let picked = FileDialog::new()
+                                .add_filter("Spreadsheets", &["xlsx", "xlsm", "xls", "ods", "csv", "tsv"])
+                                .show_open_single_file();
+                            match picked {
+                                Ok(Some(file)) => load(file.to_string_lossy().into_owned()),
+                                Ok(None) => (),
+                                Err(e) => message.set(e.to_string()),
+                            }
+                        },
+                        "Browse..."
+                    }
+                    button {
+                        onclick: move |_| {
+                            let file = path.read().clone();
+                            load(file);
+                        },
+                        "Load"
+                    }
+                }
+                if sheets.read().len() > 1 {
+                    div {
+                        class: "migration-row",
+                        b { "Sheet" }
+                        select {
+                            oninput: move |event| {
+                                sheet_index.set(event.value().parse().unwrap_or(0));
+                                overrides.write().clear();
+                            },
+                            for (i, sheet) in sheets.read().iter().enumerate() {
+                                option { value: "{ i }", selected: i == *sheet_index.read(), "{ sheet.name }" }
+                            }
+                        }
+                    }
+                }
+                if sheet.is_some() {
+                    label {
+                        input {
+                            type: "checkbox",
+                            checked: *has_header.read(),
+                            oninput: move |event| {
+                                has_header.set(event.checked());
+                                overrides.write().clear();
+                            },
+                        }
+                        " The first row holds column titles"
+                    }
+                }
+
+                h2 { "Database" }
+                div {
+                    class: "migration-row",
+                    select {
+                        oninput: move |event| {
+                            target.set(Some(event.value()));
+                            overrides.write().clear();
+                        },
+                        for name in databases.iter() {
+                            option { value: "{ name }", selected: *name == target_name, "{ name }" }
+                        }
+                        option { value: NEW_DATABASE, selected: creating, "New database..." }
+                    }
+                    if creating {
+                        input {
+                            placeholder: "Name of the new database",
+                            value: "{ new_name.read() }",
+                            oninput: move |event| new_name.set(event.value()),
+                        }
+                    }
+                }
+
+                if sheet.is_some() && !creating {
+                    h2 { "Columns" }
+                    for (column, title) in header.iter().enumerate() {
+                        div {
+                            class: "migration-row",
+                            b { if column == 0 { "{ title } (key)" } else { "{ title }" } }
+                            select {
+                                oninput: move |event| {
+                                    overrides.write().insert(column, event.value().parse::<usize>().ok());
+                                },
+                                option { value: "", selected: mapping[column].is_none(), "Leave empty" }
+                                for (i, source) in source_header.iter().enumerate() {
+                                    option { value: "{ i }", selected: mapping[column] == Some(i), "{ source }" }
+                                }
+                            }
+                        }
+                    }
+                    h2 { "Existing keys" }
+                    select {
+                        oninput: move |event| overwrite.set(event.value() == "overwrite"),
+                        option { value: "keep", selected: !*overwrite.read(), "Keep the rows already in { target_name }" }
+                        option { value: "overwrite", selected: *overwrite.read(), "Update them with the imported values" }
+                    }
+                }
+
+                if let Some(plan) = plan.clone() {
+                    h2 { "Preview" }
+                    p {
+                        "{ plan.added.len() } new, { plan.updated.len() } updated, { plan.skipped.len() } already present"
+                        if !plan.repeated.is_empty() {
+                            ", { plan.repeated.len() } repeated in the file (first occurrence used)"
+                        }
+                        if plan.keyless > 0 {
+                            ", { plan.keyless } without a key (left out)"
+                        }
+                    }
+                    if !plan.repeated.is_empty() {
+                        p { class: "warning-msg", "Repeated keys: { plan.repeated.join(\", \") }" }
+                    }
+                    div {
+                        class: "table-div",
+                        table {
+                            thead {
+                                tr {
+                                    th { "" }
+                                    for title in header.iter() {
+                                        th { "{ title }" }
+                                    }
+                                }
+                            }
+                            tbody {
+                                for row in plan.rows.iter().skip(1).filter(|row| plan.added.contains(&row[0]) || plan.updated.contains(&row[0])).take(PREVIEW_ROWS) {
+                                    tr {
+                                        td { if plan.added.contains(&row[0]) { "new" } else { "updated" } }
+                                        for cell in row.iter() {
+                                            td { "{ cell }" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                p { class: "warning-msg", "{ message.read() }" }
+                button {
+                    class: "close-button",
+                    onclick: move |_| {
+                        document::eval(r#"
+const dialog = document.getElementById("importer");
+dialog.close();"#);
+                    },
+                    "Cancel"
+                }
+                if let Some(plan) = plan {
+                    button {
+                        class: "creation-button",
+                        onclick: move |_| {
+                            let result = match creating {
+                                true => create_database(&new_name.read(), &plan.rows),
+                                false => scroll_processor::write_db(&target_name, &plan.rows).map_err(|e| e.to_string()),
+                            };
+                            match result.and_then(|_| FILE_DATA.write().refresh()) {
+                                Ok(()) => {
+                                    sheets.set(Vec::new());
+                                    path.set(String::new());
+                                    message.set(String::new());
+                                    document::eval(r#"
+const dialog = document.getElementById("importer");
+dialog.close();"#);
+                                },
+                                Err(e) => message.set(e),
+                            }
+                        },
+                        "Import"
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+/// Checks the name of a new database and writes it to `DOC_DIR/sys`
+fn create_database(name: &str, rows: &[Vec<String>]) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().any(|c| !c.is_alphanumeric() && c != '-' && c != '_') {
+        return Err("Database names can only have letters, digits, '-' and '_'.".to_string());
+    }
+    if rows.first().is_none_or(|header| header.iter().any(|title| title.trim().is_empty())) {
+        return Err("Every column needs a title; tick \"The first row holds column titles\" or add them to the file.".to_string());
+    }
+    scroll_processor::create_db(name, rows).map_err(|e| e.to_string())
+}
@@ -17,8 +17,13 @@ mod home;
 mod file_explorer;
 mod metadata_popup;
 mod db_popup;
+mod combobox;
+mod migration_popup;
+mod import_popup;
+mod search_popup;
 mod tools;
 mod types;
+mod watcher;
 
 use crate::{
     load::Loader,
@@ -1,8 +1,11 @@
 use crate::{
     prelude::*,
+    tools::{self, auto_fields},
+    combobox::Combobox,
     db_popup::PopupOpener,
     types::generator::*,
 };
+use serde_json::Value;
 use std::fs::{File, write, remove_file};
 
 
@@ -125,11 +128,6 @@ fn error_checker() {
     let state_binding = &mut POPUP_GENERATOR.write().state;
 
     let attributes = &FILE_DATA.read().attributes;
-    let required: Vec<(bool, String)> = attributes.iter()
-        .map(|(title, element)| {
-            (element.is_req(), title.clone())
-        })
-        .collect();
 
     // Start by clearing previous errors
     *state_binding = CreatorState::Ok;
@@ -139,13 +137,26 @@ fn error_checker() {
         state_binding.file_error();
     }
 
-    // Check for required elements that are empty
-    for id in 0..required.len() {
-        assert!(required.get(id).is_some() && metadata_binding.get(id).is_some(), "Metadata struct size does not match attribute struct");
-        let (is_required, title) = required.get(id).unwrap();
+    for (id, (title, element, _)) in attributes.iter().enumerate() {
+        assert!(metadata_binding.get(id).is_some(), "Metadata struct size does not match attribute struct");
         let metadata = metadata_binding.get(id).unwrap();
-        if *is_required && metadata.is_empty() {
-            state_binding.component_error(title);
+
+        // Auto-populated elements are only assigned on save
+        if element.is_auto() {
+            continue;
+        }
+
+        // Check for required elements that are empty
+        if element.is_req() && metadata.is_empty() {
+            state_binding.component_error(title, "This field is required.");
+            continue;
+        }
+
+        // Check for filled elements that break their format rules
+        if !metadata.is_empty() {
+            if let Err(message) = element.validate(metadata) {
+                state_binding.component_error(title, &message);
+            }
         }
     }
 }
@@ -169,7 +180,12 @@ fn ElementCreator(id: usize) -> Element {
         *write_binding = value;
     };
 
-    let (mut title, elem) = attr_ref.unwrap().clone();
+    // The attribute's position in the form, before `id` is shadowed by database names below
+    let field_index = id;
+    let (mut title, elem, info) = attr_ref.unwrap().clone();
+    let placeholder = info.placeholder.clone().unwrap_or_default();
+    let empty = FieldValue::empty(&elem);
+    let error = POPUP_GENERATOR.read().state.message(&title);
 
     if elem.is_req() {
         title.push('*');
@@ -179,14 +195,45 @@ fn ElementCreator(id: usize) -> Element {
     rsx! {
         div {
             class: "metadata-div",
-            h2 { "{ title }" }
+            h2 {
+                title: info.help.clone().unwrap_or_default(),
+                "{ title }"
+                if let Some(origin) = info.origin.label() {
+                    span { class: "attribute-origin", "{ origin }" }
+                }
+            }
+            if let Some(help) = info.help.clone() {
+                p { class: "field-help", "{ help }" }
+            }
             match element_type {
                 InputField::String { .. } => {
                     rsx! {
                         textarea {
                             rows: "4",
+                            placeholder: "{ placeholder }",
+                            value: "{ display }",
+                            oninput: move |event| { binding(FieldValue::Text(event.value())); } }
+                    }
+                },
+                InputField::Number { .. } => {
+                    rsx! {
+                        input {
+                            type: "number",
+                            step: "any",
+                            placeholder: "{ placeholder }",
                             value: "{ display }",
-                            oninput: move |event| { binding(event.value()); } }
+                            oninput: move |event| {
+                                let value = event.value();
+                                binding(value.parse::<f64>().map(FieldValue::Number).unwrap_or(FieldValue::Text(value)));
+                            } }
+                    }
+                },
+                InputField::Bool { .. } => {
+                    rsx! {
+                        input {
+                            type: "checkbox",
+                            checked: display == FieldValue::Bool(true),
+                            oninput: move |event| { binding(FieldValue::Bool(event.checked())); } }
                     }
                 },
                 InputField::Date { .. } => {
@@ -194,36 +241,143 @@ fn ElementCreator(id: usize) -> Element {
                         input {
                             type: "date",
                             value: "{ display }",
-                            oninput: move |event| { binding(event.value()); } }
-                        button { onclick: move |_| { binding(String::new()); }, "Clear" },
+                            oninput: move |event| { binding(FieldValue::Date(event.value())); } }
+                        button { onclick: move |_| { binding(empty.clone()); }, "Clear" },
+                    }
+                },
+                InputField::DateTime { .. } => {
+                    let local = tools::time::datetime_to_input(&display.to_string());
+                    rsx! {
+                        input {
+                            type: "datetime-local",
+                            value: "{ local }",
+                            oninput: move |event| {
+                                let value = event.value();
+                                binding(FieldValue::Date(tools::time::datetime_to_iso(&value).unwrap_or(value)));
+                            } }
+                        button { onclick: move |_| { binding(empty.clone()); }, "Clear" },
+                        if !display.is_empty() {
+                            p { b {"Stored as: "} " { display }" }
+                        }
+                    }
+                },
+                InputField::Duration { .. } => {
+                    let parsed = tools::time::duration_to_display(&display.to_string());
+                    rsx! {
+                        input {
+                            placeholder: info.placeholder.clone().unwrap_or("2h30m".to_string()),
+                            value: "{ display }",
+                            oninput: move |event| { binding(FieldValue::Text(event.value())); } }
+                        if !display.is_empty() && tools::time::parse_duration(&display.to_string()).is_some() {
+                            p { b {"Duration: "} " { parsed }" }
+                        }
                     }
                 },
                 InputField::One { id, .. } => {
-                    let options = scroll_processor::db_query(&id).unwrap().1;
+                    rsx! {
+                        Combobox {
+                            id: id.clone(),
+                            value: display.clone(),
+                            multiple: false,
+                            on_change: move |value| { binding(value); },
+                        }
+                        PopupOpener { id: id, target: field_index }
+                    }
+                },
+                InputField::Multi { id, .. } => {
+                    rsx! {
+                        Combobox {
+                            id: id.clone(),
+                            value: display.clone(),
+                            multiple: true,
+                            on_change: move |value| { binding(value); },
+                        }
+                        button { onclick: move |_| { binding(empty.clone()); }, "Clear" }
+                        PopupOpener { id: id, target: field_index }
+                    }
+                },
+                InputField::Url { .. } => {
+                    rsx! {
+                        input {
+                            type: "url",
+                            placeholder: info.placeholder.clone().unwrap_or("https://".to_string()),
+                            value: "{ display }",
+                            oninput: move |event| { binding(FieldValue::Text(event.value())); } }
+                    }
+                },
+                InputField::Doi { .. } => {
+                    rsx! {
+                        input {
+                            placeholder: info.placeholder.clone().unwrap_or("10.1000/xyz123".to_string()),
+                            value: "{ display }",
+                            oninput: move |event| { binding(FieldValue::Text(event.value())); } }
+                    }
+                },
+                InputField::Ref { dir, .. } => {
+                    let options = scroll_processor::ref_query(&dir).unwrap_or_default();
                     rsx! {
                         select {
-                            oninput: move |event| { binding(event.value()); },
+                            oninput: move |event| { binding(FieldValue::Text(event.value())); },
                             option { value: "", "" }
                             for option in options.iter() {
-                                option { value: "{ option }", selected: *option == display, "{ option }" }
+                                option { value: "{ option }", selected: *option == display.to_string(), "{ deserialize(option) }" }
                             }
                         }
-                        PopupOpener { id: id }
+                        p { b {"From: "} " { dir }" }
                     }
                 },
-                InputField::Multi { id, .. } => {
-                    let options = scroll_processor::db_query(&id).unwrap().1;
+                InputField::Refs { dir, .. } => {
+                    let options = scroll_processor::ref_query(&dir).unwrap_or_default();
+                    rsx! {
+                        select {
+                            oninput: move |event| {
+                                let addition = event.value();
+                                let mut selections = display.items();
+                                if !selections.contains(&addition) {
+                                    selections.push(addition);
+                                    binding(FieldValue::List(selections));
+                                };
+                            },
+                            option { disabled: true, selected: true, "Add..." }
+                            for option in options.iter() {
+                                option { value: "{ option }", "{ deserialize(option) }" }
+                            }
+                        }
+                        button { onclick: move |_| { binding(empty.clone()); }, "Clear" }
+                        p { b {"From: "} " { dir }" }
+                        p { b {"Selected: "} " { display }" }
+                    }
+                },
+                InputField::Created | InputField::Modified | InputField::Author | InputField::Seq { .. } => {
+                    let shown = element_type.display(&display);
+                    rsx! {
+                        if display.is_empty() {
+                            p { i { "Assigned automatically on save" } }
+                        } else {
+                            p { "{ shown }" }
+                        }
+                    }
+                },
+                InputField::Choice { options, .. } => {
+                    rsx! {
+                        select {
+                            oninput: move |event| { binding(FieldValue::Text(event.value())); },
+                            option { value: "", "" }
+                            for option in options.iter() {
+                                option { value: "{ option }", selected: *option == display.to_string(), "{ option }" }
+                            }
+                        }
+                    }
+                },
+                InputField::Choices { options, .. } => {
                     rsx! {
                         select {
                             oninput: move |event| {
                                 let addition = event.value();
-                                let mut selections: Vec<&str> = display.split(", ").collect();
-                                if !selections.contains(&addition.as_str()) {
-                                    selections.push(&addition);
-                                    if selections.get(0) == Some(&"") {
-                                        selections.remove(0);
-                                    }
-                                    binding(selections.join(", "));
+                                let mut selections = display.items();
+                                if !selections.contains(&addition) {
+                                    selections.push(addition);
+                                    binding(FieldValue::List(selections));
                                 };
                             },
                             option { disabled: true, selected: true, "Add..." }
@@ -231,12 +385,14 @@ fn ElementCreator(id: usize) -> Element {
                                 option { value: "{ option }", "{ option }" }
                             }
                         }
-                        button { onclick: move |_| { binding(String::new()); }, "Clear" }
-                        PopupOpener { id: id }
+                        button { onclick: move |_| { binding(empty.clone()); }, "Clear" }
                         p { b {"Selected: "} " { display }" }
                     }
                 }
             }
+            if let Some(message) = error {
+                p { class: "field-error", "{ message }" }
+            }
         }
     }
 }
@@ -252,11 +408,15 @@ fn Submission() -> Element {
                 true => rsx! { Rebase {} },
             }
         },
-        CreatorState::Err { error } => {
-            let output_string = error.join(", ");
+        CreatorState::Err { file_name, error } => {
+            let output_string = file_name.then(|| "File Name".to_string())
+                .into_iter()
+                .chain(error.iter().map(|(title, _)| title.clone()))
+                .collect::<Vec<String>>()
+                .join(", ");
             rsx! {
                 h3 { "⚠️ WARNING!" }
-                p { "The following required fields are empty or invalid: { output_string }" }
+                p { "Please fix the highlighted fields before saving: { output_string }" }
             }
         },
     }
@@ -331,16 +491,17 @@ fn laid_in_state() -> Result<()> {
     let metadata_json_binding = json_processor::get_json_hashmap(&db_path);
     assert!(metadata_json_binding.is_ok(), "Invalid metadata, yet file creator called");
     let metadata_json = metadata_json_binding.as_ref().unwrap();
-    let mut metadata: Vec<Vec<(String, String)>> = json_processor::hashmap_to_vec(metadata_json);
+    let mut metadata: Vec<Vec<(String, Value)>> = json_processor::hashmap_to_vec(metadata_json);
 
     let mut new_filename = context.read().filename.clone();
     new_filename = serialize(&new_filename);
     let new_metadata = &context.read().metadata;
 
-    let mut new_vector = vec![("__ID".to_string(), new_filename.clone())];
+    let mut new_vector = vec![("__ID".to_string(), Value::String(new_filename.clone()))];
     assert!(attributes.len() == new_metadata.len(), "Attribute and metadata vectors do not match");
-    for ((title, _), metadata) in attributes.iter().zip(new_metadata.iter()) {
-        new_vector.push((title.clone(), metadata.clone()));
+    let mut reserved = Vec::new();
+    for ((title, element, _), metadata) in attributes.iter().zip(new_metadata.iter()) {
+        new_vector.push((title.clone(), element.normalize(auto_fields::fill(element, metadata, true, &mut reserved)?).to_json()));
     }
     metadata.push(new_vector);
 
@@ -350,6 +511,7 @@ fn laid_in_state() -> Result<()> {
     let file_path = current_path.clone().join(&new_filename).with_extension("md");
     File::create(file_path)?;
     write(db_path, json_string)?;
+    auto_fields::record_sequences(&reserved)?;
 
     Ok(())
 }
@@ -371,14 +533,15 @@ fn refreeze() -> Result<()> {
     let new_filename = serialize(filename_binding);
     let new_metadata = &context.metadata;
 
-    let mut new_vector = vec![("__ID".to_string(), new_filename.clone())];
+    let mut new_vector = vec![("__ID".to_string(), Value::String(new_filename.clone()))];
     assert!(attributes.len() == new_metadata.len(), "Attribute and metadata vectors do not match");
-    for ((title, _), metadata) in attributes.iter().zip(new_metadata.iter()) {
-        new_vector.push((title.clone(), metadata.clone()));
+    let mut reserved = Vec::new();
+    for ((title, element, _), metadata) in attributes.iter().zip(new_metadata.iter()) {
+        new_vector.push((title.clone(), element.normalize(auto_fields::fill(element, metadata, false, &mut reserved)?).to_json()));
     }
 
     json_processor::update_json_hashmap(&mut metadata_json, &new_filename, new_vector);
-    let mut metadata: Vec<Vec<(String, String)>> = json_processor::hashmap_to_vec(&metadata_json);
+    let mut metadata: Vec<Vec<(String, Value)>> = json_processor::hashmap_to_vec(&metadata_json);
 
     let mut json_array = json_processor::vec_to_json(&metadata);
     let json_string = serde_json::to_string_pretty(&json_array)?;
@@ -386,6 +549,7 @@ fn refreeze() -> Result<()> {
     let file_path = current_path.clone().join(&new_filename).with_extension("md");
     File::create(file_path)?;
     write(db_path, json_string)?;
+    auto_fields::record_sequences(&reserved)?;
 
     Ok(())
 }
@@ -434,7 +598,7 @@ fn fall_out_of_window() -> Result<()> {
     let mut metadata_json = metadata_json_binding.unwrap();
     json_processor::delete_from_hashmap(&mut metadata_json, &filename);
 
-    let mut metadata: Vec<Vec<(String, String)>> = json_processor::hashmap_to_vec(&metadata_json);
+    let mut metadata: Vec<Vec<(String, Value)>> = json_processor::hashmap_to_vec(&metadata_json);
     let mut json_array = json_processor::vec_to_json(&metadata);
     let json_string = serde_json::to_string_pretty(&json_array)?;
     write(db_path, json_string)?;
@@ -546,7 +710,7 @@ fn rename(old_name: String, new_name: String) -> Result<()> {
     let mut metadata_json = metadata_json_binding.unwrap();
     json_processor::rename_in_hashmap(&mut metadata_json, &old_name, &serialize(&new_name));
 
-    let mut metadata: Vec<Vec<(String, String)>> = json_processor::hashmap_to_vec(&metadata_json);
+    let mut metadata: Vec<Vec<(String, Value)>> = json_processor::hashmap_to_vec(&metadata_json);
     let mut json_array = json_processor::vec_to_json(&metadata);
     let json_string = serde_json::to_string_pretty(&json_array)?;
 
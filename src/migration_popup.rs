@@ -0,0 +1,212 @@
+use crate::{
+    prelude::*,
+    tools::migration::{self, MigrationStep},
+};
+
+
+
+static KEEP: &str = "__KEEP";
+static DROP: &str = "__DROP";
+
+
+
+/// Notice shown above the file table when records and schema have drifted apart
+pub fn MigrationBanner() -> Element {
+    let diff = FILE_DATA.read().schema_diff.clone();
+    let legacy = FILE_DATA.read().legacy_values;
+    rsx! {
+        if legacy {
+            UpgradeBanner {}
+        }
+        if !diff.is_empty() {
+            div {
+                class: "migration-banner",
+                span {
+                    "The attributes of this directory changed: { diff.added.len() } new, { diff.removed.len() } no longer declared."
+                }
+                button {
+                    onclick: move |_| {
+                        document::eval(r#"
+const dialog = document.getElementById("migrator");
+dialog.showModal();"#);
+                    },
+                    "Review changes"
+                }
+            }
+        }
+    }
+}
+
+
+/// Offers to rewrite values stored by older versions of Scroll in their typed form
+///
+/// The records already show correctly; upgrading only changes how `.database.json` is written, so it is
+/// left to the user to do once, e.g. when nobody else has unpushed changes to the directory.
+fn UpgradeBanner() -> Element {
+    let mut message = use_signal(String::new);
+    rsx! {
+        div {
+            class: "migration-banner",
+            span { "Some records were written by an older version of Scroll and store lists and numbers as text." }
+            button {
+                onclick: move |_| {
+                    let db_path = FILE_DATA.read().current_path.join(".database.json");
+                    let attributes = FILE_DATA.read().attributes.clone();
+                    let result = migration::upgrade_values(&db_path, &attributes)
+                        .map_err(|e| e.to_string())
+                        .and_then(|_| FILE_DATA.write().refresh());
+                    match result {
+                        Ok(()) => message.set(String::new()),
+                        Err(e) => message.set(e),
+                    }
+                },
+                "Upgrade records"
+            }
+            if !message.read().is_empty() {
+                span { class: "field-error", "{ message.read() }" }
+            }
+        }
+    }
+}
+
+
+/// Dialog for backfilling, renaming and dropping fields across `.database.json` in one step
+#[component]
+pub fn Migrator() -> Element {
+    let diff = FILE_DATA.read().schema_diff.clone();
+    let attributes = FILE_DATA.read().attributes.clone();
+    let mut decisions: Signal<HashMap<String, String>> = use_signal(HashMap::new);
+    let mut backfills: Signal<HashMap<String, String>> = use_signal(HashMap::new);
+    let mut message = use_signal(String::new);
+
+    // Choices the user has not touched fall back to suggestions and declared defaults
+    let decision_for = {
+        let diff = diff.clone();
+        move |key: &str| -> String {
+            decisions.read().get(key).cloned()
+                .or(diff.rename_suggestion(key))
+                .unwrap_or(KEEP.to_string())
+        }
+    };
+    let backfill_for = {
+        let attributes = attributes.clone();
+        move |title: &str| -> String {
+            backfills.read().get(title).cloned()
+                .or(attributes.iter()
+                    .find(|(existing, _, _)| existing == title)
+                    .and_then(|(_, _, info)| info.default.clone()))
+                .unwrap_or_default()
+        }
+    };
+
+    let steps: Vec<MigrationStep> = {
+        let mut steps: Vec<MigrationStep> = diff.removed.iter()
+            .filter_map(|key| {
+                let decision = decision_for(key);
+                if decision == DROP {
+                    Some(MigrationStep::Drop { key: key.clone() })
+                } else if decision == KEEP {
+                    None
+                } else {
+                    Some(MigrationStep::Rename { from: key.clone(), to: decision })
+                }
+            })
+            .collect();
+        steps.extend(attributes.iter()
+            .filter(|(title, _, _)| diff.added.contains(title))
+            .map(|(title, field, _)| MigrationStep::Backfill {
+                attribute: title.clone(),
+                value: FieldValue::from_input(field, &backfill_for(title)),
+            }));
+        steps
+    };
+
+    rsx! {
+        div {
+            class: "creator-popup",
+            dialog {
+                id: "migrator",
+                div {
+                    class: "metadata-div",
+                    h1 { "Migrate records" }
+                    p { "Changes are written to every entry's record in .database.json; Markdown files are not touched." }
+                }
+                if !diff.removed.is_empty() {
+                    div {
+                        class: "metadata-div",
+                        h2 { "Fields no longer declared" }
+                        for key in diff.removed.iter().cloned() {
+                            div {
+                                class: "migration-row",
+                                b { "{ key }" }
+                                select {
+                                    oninput: {
+                                        let key = key.clone();
+                                        move |event: FormEvent| { decisions.write().insert(key.clone(), event.value()); }
+                                    },
+                                    option { value: KEEP, selected: decision_for(&key) == KEEP, "Keep for now" }
+                                    option { value: DROP, selected: decision_for(&key) == DROP, "Drop from all records" }
+                                    for title in diff.added.iter() {
+                                        option { value: "{ title }", selected: decision_for(&key) == *title, "Rename to { title }" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                if !diff.added.is_empty() {
+                    div {
+                        class: "metadata-div",
+                        h2 { "New attributes" }
+                        p { "Records without a value receive the one below. Renamed fields keep their existing values." }
+                        for title in diff.added.iter().cloned() {
+                            div {
+                                class: "migration-row",
+                                b { "{ title }" }
+                                input {
+                                    value: "{ backfill_for(&title) }",
+                                    placeholder: "Leave empty",
+                                    oninput: {
+                                        let title = title.clone();
+                                        move |event: FormEvent| { backfills.write().insert(title.clone(), event.value()); }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                br {}
+                button {
+                    class: "close-button",
+                    onclick: move |_| {
+                        document::eval(r#"
+const dialog = document.getElementById("migrator");
+dialog.close();"#);
+                    },
+                    "Cancel" }
+                button {
+                    class: "creation-button",
+                    onclick: move |_| {
+                        let db_path = FILE_DATA.read().current_path.join(".database.json");
+                        match migration::migrate(&db_path, &steps)
+                            .map_err(|e| e.to_string())
+                            .and_then(|_| FILE_DATA.write().refresh()) {
+                            Ok(()) => {
+                                decisions.write().clear();
+                                backfills.write().clear();
+                                message.set(String::new());
+                                document::eval(r#"
+const dialog = document.getElementById("migrator");
+dialog.close();"#);
+                            }
+                            Err(e) => {
+                                message.set(e.to_string());
+                            }
+                        }
+                    },
+                    "Apply" }
+                p { class: "warning-msg", "{ message.read() }" }
+            }
+        }
+    }
+}
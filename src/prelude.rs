@@ -18,6 +18,7 @@ pub use crate::{
         aliases::*,
         input::*,
         statics::*,
+        value::FieldValue,
         generator::FileGenerator,
         files::FileData,
     },
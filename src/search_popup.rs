@@ -0,0 +1,142 @@
+use crate::{
+    prelude::*,
+    tools::search::{self, Hit},
+};
+
+
+
+/// Most results listed for a search
+const MAX_RESULTS: usize = 30;
+
+
+
+/// Brings the search index up to date with the workspace, in the background
+///
+/// Only directories changed since the last run are read again, so later starts are quick.
+pub fn use_search_index() {
+    use_hook(|| {
+        spawn(async move {
+            let result = tokio::task::spawn_blocking(|| search::sync().map(|_| ())).await;
+            report_indexing(result);
+        });
+    });
+}
+
+
+/// Keeps the outcome of an index update in `SEARCH_ERROR`, clearing it once indexing works again
+pub fn report_indexing(result: Result<Result<()>, tokio::task::JoinError>) {
+    let error = match result {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(format!("The search index could not be updated, so results may be out of date. {e}")),
+        Err(e) => Some(format!("The search index could not be updated, so results may be out of date. {e}")),
+    };
+    if *SEARCH_ERROR.read() != error {
+        *SEARCH_ERROR.write() = error;
+    }
+}
+
+
+pub fn SearchButton() -> Element {
+    rsx! {
+        button {
+            title: "Search every entry of the workspace",
+            onclick: move |_| {
+                document::eval(r#"
+const dialog = document.getElementById("search-popup");
+dialog.showModal();
+dialog.querySelector("input").focus();"#);
+            },
+            "Search"
+        }
+    }
+}
+
+
+/// Workspace-wide search over entry names, bodies and metadata
+///
+/// Picking a result opens the directory holding the entry.
+#[component]
+pub fn SearchPopup() -> Element {
+    let mut text = use_signal(String::new);
+    let mut hits: Signal<Vec<Hit>> = use_signal(Vec::new);
+    let mut message = use_signal(String::new);
+    let root = DOC_DIR.read().unwrap().clone();
+
+    rsx! {
+        dialog {
+            id: "search-popup",
+            class: "creator-popup",
+            div {
+                class: "metadata-div",
+                h1 { "Search" }
+                input {
+                    class: "db-search",
+                    placeholder: "Words to find, \"exact phrase\" or metadata like Owner",
+                    value: "{ text.read() }",
+                    oninput: move |event| {
+                        text.set(event.value());
+                        match search::search(&event.value(), MAX_RESULTS) {
+                            Ok(v) => {
+                                hits.set(v);
+                                message.set(String::new());
+                            },
+                            Err(e) => message.set(e.to_string()),
+                        }
+                    },
+                }
+                if let Some(e) = SEARCH_ERROR.read().as_ref() {
+                    p { class: "warning-msg", "⚠️ { e }" }
+                }
+                p { class: "warning-msg", "{ message.read() }" }
+                if hits.read().is_empty() && !text.read().trim().is_empty() && message.read().is_empty() {
+                    p { "No entries match." }
+                }
+                ul {
+                    class: "search-results",
+                    for hit in hits.read().iter().cloned() {
+                        li {
+                            onclick: {
+                                let dir = hit.dir.clone();
+                                move |_| {
+                                    if dir.is_dir() {
+                                        FILE_DATA.write().goto(&dir);
+                                        POPUP_GENERATOR.write().refresh();
+                                    }
+                                    document::eval(r#"
+const dialog = document.getElementById("search-popup");
+dialog.close();"#);
+                                }
+                            },
+                            b { "{ deserialize(&hit.entry) }" }
+                            span {
+                                class: "search-location",
+                                "{ hit.dir.strip_prefix(&root).unwrap_or(&hit.dir).display() }"
+                            }
+                            if !hit.snippet.is_empty() {
+                                p {
+                                    class: "search-snippet",
+                                    for (piece, highlighted) in hit.snippet.iter().cloned() {
+                                        if highlighted {
+                                            mark { "{ piece }" }
+                                        } else {
+                                            "{ piece }"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                button {
+                    class: "close-button",
+                    onclick: move |_| {
+                        document::eval(r#"
+const dialog = document.getElementById("search-popup");
+dialog.close();"#);
+                    },
+                    "Close"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{
+    prelude::*,
+    tools::workspace,
+    types::files::load_attributes,
+};
+use chrono::Local;
+use serde_json::{Map, Value};
+use std::{
+    env,
+    fs::{read_to_string, write},
+    process::Command,
+};
+
+
+
+/// Fills an auto-populated attribute when an entry is written
+///
+/// # Props
+/// - `field`: The attribute type
+/// - `current`: The value currently held by the entry
+/// - `creating`: Whether the entry is being created rather than updated
+/// - `reserved`: Sequence numbers handed out for the entry so far, to pass to [`record_sequences`] once it is saved
+///
+/// # Returns
+/// - `Ok` with the value to store. Non-auto attributes and already-assigned values are returned unchanged,
+///   except `Modified`, which is refreshed on every write.
+/// - `Err(e)` if a sequence number could not be computed
+pub fn fill(field: &InputField, current: &FieldValue, creating: bool, reserved: &mut Vec<(String, u64)>) -> Result<FieldValue> {
+    let keep = !current.is_empty() && !creating;
+    Ok(match field {
+        InputField::Modified => FieldValue::Date(now()),
+        InputField::Created if !keep => FieldValue::Date(now()),
+        InputField::Author if !keep => FieldValue::Text(author()),
+        InputField::Seq { pattern } if !keep => FieldValue::Text(next_sequence(pattern, reserved)?),
+        _ => current.clone(),
+    })
+}
+
+
+fn now() -> String {
+    Local::now().format("%Y-%m-%dT%H:%M:%S%:z").to_string()
+}
+
+
+/// Finds the name of the person running Scroll
+///
+/// Uses the Git identity of the workspace, falling back to the system user name.
+pub fn author() -> String {
+    let git_name = Command::new("git")
+        .arg("-C")
+        .arg(DOC_DIR.read().unwrap().as_os_str())
+        .args(["config", "user.name"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|name| !name.is_empty());
+    git_name
+        .or(env::var("USER").ok())
+        .or(env::var("USERNAME").ok())
+        .unwrap_or("Unknown".to_string())
+}
+
+
+/// Splits a sequence pattern such as `EXP-####` around its run of `#`
+///
+/// # Returns
+/// - `Some((prefix, width, suffix))` if the pattern has exactly one run of `#`
+/// - `None` otherwise
+pub fn split_pattern(pattern: &str) -> Option<(&str, usize, &str)> {
+    let start = pattern.find('#')?;
+    let width = pattern[start..].chars().take_while(|c| *c == '#').count();
+    let suffix = &pattern[start + width..];
+    if suffix.contains('#') {
+        return None;
+    }
+    Some((&pattern[..start], width, suffix))
+}
+
+
+/// Computes the next identifier of a sequence and reserves it for the entry being written
+///
+/// Only attributes declared as `Seq` with the same pattern are scanned, in every directory of the workspace,
+/// along with the numbers recorded in `sys/.sequences.json` and those already in `reserved`. Nothing is written
+/// here, so a cancelled or failed save gives the number back.
+///
+/// # Returns
+/// - `Ok` with the formatted identifier
+/// - `Err(e)` if the pattern is invalid, the sequence is exhausted or `sys/.sequences.json` cannot be read
+fn next_sequence(pattern: &str, reserved: &mut Vec<(String, u64)>) -> Result<String> {
+    let (prefix, width, suffix) = split_pattern(pattern)
+        .ok_or(Report::msg(format!("Invalid sequence pattern {pattern}")))?;
+
+    let used = workspace::database_dirs()
+        .par_iter()
+        .filter_map(|dir| {
+            let titles: Vec<String> = load_attributes(dir, &mut Vec::new()).ok()?
+                .into_iter()
+                .filter(|(_, field, _)| matches!(field, InputField::Seq { pattern: other } if other == pattern))
+                .map(|(title, _, _)| title)
+                .collect();
+            let objects = json_processor::get_json_hashmap(&dir.join(".database.json")).ok()?;
+            Some(objects.into_values()
+                .flat_map(|map| titles.iter().filter_map(|title| map.get(title).cloned()).collect::<Vec<Value>>())
+                .collect::<Vec<Value>>())
+        })
+        .flatten()
+        .filter_map(|value| {
+            value.as_str()?
+                .strip_prefix(prefix)?
+                .strip_suffix(suffix)?
+                .parse::<u64>()
+                .ok()
+        })
+        .max()
+        .unwrap_or(0);
+
+    let recorded = read_sequences()?.get(pattern).and_then(|v| v.as_u64()).unwrap_or(0);
+    let pending = reserved.iter()
+        .filter(|(other, _)| other == pattern)
+        .map(|(_, number)| *number)
+        .max()
+        .unwrap_or(0);
+    let next = used.max(recorded).max(pending).checked_add(1)
+        .ok_or(Report::msg(format!("The sequence {pattern} has no numbers left")))?;
+    reserved.push((pattern.to_string(), next));
+
+    Ok(format!("{prefix}{:0width$}{suffix}", next))
+}
+
+
+/// Records the sequence numbers of a saved entry in `sys/.sequences.json`
+///
+/// The file makes sure a number is never given out twice by this clone, even once its entry is deleted, and
+/// two clones handing out the same number meet as a merge conflict on it.
+pub fn record_sequences(reserved: &[(String, u64)]) -> Result<()> {
+    if reserved.is_empty() {
+        return Ok(());
+    }
+    let mut sequences = read_sequences()?;
+    for (pattern, number) in reserved {
+        let recorded = sequences.get(pattern).and_then(|v| v.as_u64()).unwrap_or(0);
+        sequences.insert(pattern.clone(), Value::from(recorded.max(*number)));
+    }
+    write(sequences_path(), serde_json::to_string_pretty(&Value::Object(sequences))?)?;
+    Ok(())
+}
+
+
+fn sequences_path() -> PathBuf {
+    DOC_DIR.read().unwrap().join("sys/.sequences.json")
+}
+
+
+/// Reads the last number recorded for each sequence pattern, or none if the file does not exist yet
+fn read_sequences() -> Result<Map<String, Value>> {
+    let path = sequences_path();
+    match read_to_string(&path) {
+        Ok(text) => match serde_json::from_str(&text)? {
+            Value::Object(map) => Ok(map),
+            _ => Err(Report::msg(format!("{} is not an object", path.display()))),
+        },
+        Err(_) => Ok(Map::new()),
+    }
+}
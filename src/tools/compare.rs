@@ -1,10 +1,149 @@
-pub fn increasing(a: &Vec<String>, b: &Vec<String>, id: usize) -> std::cmp::Ordering {
+use crate::{
+    tools::time,
+    types::{input::InputField, value::FieldValue},
+};
+use chrono::NaiveDate;
+use std::cmp::Ordering;
+
+
+
+/// Where rows with an empty value in the sorted column go, whichever the direction
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum EmptyPlacement {
+    First,
+    #[default]
+    Last,
+}
+
+
+
+pub fn increasing(a: &Vec<FieldValue>, b: &Vec<FieldValue>, id: usize, field: Option<&InputField>, empties: EmptyPlacement) -> Ordering {
     assert!(a.get(id).is_some() && b.get(id).is_some(), "Compared vectors have no zeroth element");
-    a.get(id).unwrap().cmp(&b.get(id).unwrap())
+    compare_present(a.get(id).unwrap(), b.get(id).unwrap(), empties)
+        .unwrap_or_else(|| compare_values(a.get(id).unwrap(), b.get(id).unwrap(), field))
 }
 
 
-pub fn decreasing(a: &Vec<String>, b: &Vec<String>, id: usize) -> std::cmp::Ordering {
+pub fn decreasing(a: &Vec<FieldValue>, b: &Vec<FieldValue>, id: usize, field: Option<&InputField>, empties: EmptyPlacement) -> Ordering {
     assert!(a.get(id).is_some() && b.get(id).is_some(), "Compared vectors have no zeroth element");
-    b.get(id).unwrap().cmp(&a.get(id).unwrap())
+    compare_present(a.get(id).unwrap(), b.get(id).unwrap(), empties)
+        .unwrap_or_else(|| compare_values(b.get(id).unwrap(), a.get(id).unwrap(), field))
+}
+
+
+/// Orders empty values against filled ones
+///
+/// # Returns
+/// - `Some(ordering)` if at least one of the values is empty
+/// - `None` if both are filled and have to be compared by type
+fn compare_present(a: &FieldValue, b: &FieldValue, empties: EmptyPlacement) -> Option<Ordering> {
+    let ordering = match (a.is_empty(), b.is_empty()) {
+        (false, false) => return None,
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+    };
+    match empties {
+        EmptyPlacement::Last => Some(ordering),
+        EmptyPlacement::First => Some(ordering.reverse()),
+    }
+}
+
+
+/// Compares two stored values according to the type of their column
+///
+/// Date-times are compared as instants, so values stored with different offsets still sort chronologically.
+/// Values that cannot be read as their column's type sort after those that can, and text is compared naturally.
+pub fn compare_values(a: &FieldValue, b: &FieldValue, field: Option<&InputField>) -> Ordering {
+    match (a, b, field) {
+        (FieldValue::Number(x), FieldValue::Number(y), _) => x.total_cmp(y),
+        (FieldValue::Bool(x), FieldValue::Bool(y), _) => x.cmp(y),
+        (_, _, Some(InputField::Number { .. })) => {
+            let number = |value: &FieldValue| match value {
+                FieldValue::Number(v) => Some(*v),
+                other => other.to_string().trim().parse::<f64>().ok(),
+            };
+            compare_parsed(number(a), number(b), |x, y| x.total_cmp(y))
+                .unwrap_or_else(|| natural(&a.to_string(), &b.to_string()))
+        },
+        (_, _, Some(InputField::Date { .. })) => {
+            let date = |value: &FieldValue| NaiveDate::parse_from_str(value.to_string().trim(), "%Y-%m-%d").ok();
+            compare_parsed(date(a), date(b), |x, y| x.cmp(y))
+                .unwrap_or_else(|| natural(&a.to_string(), &b.to_string()))
+        },
+        (_, _, Some(InputField::DateTime { .. } | InputField::Created | InputField::Modified)) => {
+            let datetime = |value: &FieldValue| time::parse_datetime(&value.to_string());
+            compare_parsed(datetime(a), datetime(b), |x, y| x.cmp(y))
+                .unwrap_or_else(|| natural(&a.to_string(), &b.to_string()))
+        },
+        (_, _, Some(InputField::Duration { .. })) => {
+            let duration = |value: &FieldValue| time::parse_duration(&value.to_string());
+            compare_parsed(duration(a), duration(b), |x, y| x.cmp(y))
+                .unwrap_or_else(|| natural(&a.to_string(), &b.to_string()))
+        },
+        (FieldValue::List(x), FieldValue::List(y), _) => {
+            x.iter().zip(y.iter())
+                .map(|(x, y)| natural(x, y))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(x.len().cmp(&y.len()))
+        },
+        _ => natural(&a.to_string(), &b.to_string()),
+    }
+}
+
+
+/// Compares two parsed values, with unparsed ones after parsed ones
+///
+/// # Returns
+/// `None` if neither value could be parsed
+fn compare_parsed<T>(a: Option<T>, b: Option<T>, compare: impl Fn(&T, &T) -> Ordering) -> Option<Ordering> {
+    match (a, b) {
+        (Some(x), Some(y)) => Some(compare(&x, &y)),
+        (Some(_), None) => Some(Ordering::Less),
+        (None, Some(_)) => Some(Ordering::Greater),
+        (None, None) => None,
+    }
+}
+
+
+/// Compares text the way people read it: runs of digits by their value and letters ignoring case
+///
+/// "Run_2" comes before "Run_10", and "apple" before "Banana". Texts equal under these rules fall back
+/// to plain comparison so the order stays total.
+pub fn natural(a: &str, b: &str) -> Ordering {
+    let mut x = a.chars().peekable();
+    let mut y = b.chars().peekable();
+    loop {
+        match (x.peek().copied(), y.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(c), Some(d)) if c.is_ascii_digit() && d.is_ascii_digit() => {
+                let take_digits = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                        digits.push(*c);
+                        chars.next();
+                    }
+                    digits
+                };
+                let m = take_digits(&mut x);
+                let n = take_digits(&mut y);
+                let (m_trimmed, n_trimmed) = (m.trim_start_matches('0'), n.trim_start_matches('0'));
+                let ordering = m_trimmed.len().cmp(&n_trimmed.len())
+                    .then_with(|| m_trimmed.cmp(n_trimmed));
+                if ordering.is_ne() {
+                    return ordering;
+                }
+            },
+            (Some(c), Some(d)) => {
+                let ordering = c.to_lowercase().cmp(d.to_lowercase());
+                if ordering.is_ne() {
+                    return ordering;
+                }
+                x.next();
+                y.next();
+            },
+        }
+    }
 }
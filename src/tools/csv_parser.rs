@@ -0,0 +1,204 @@
+//! RFC 4180 reader for the tables in `DOC_DIR/sys`
+//!
+//! ```text
+//! Name, Email, Group
+//! "Smith, J.", js@lab.org, "The ""Core"" team"
+//! ```
+//!
+//! Besides the RFC, a leading byte order mark is dropped, blank lines are skipped and spaces around
+//! unquoted fields are trimmed, so tables written as `a, b, c` keep working.
+use std::fmt;
+
+
+
+/// A problem in a table, located by 1-based line and column
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+
+
+/// Picks the delimiter of a table from its extension, falling back to its first line
+///
+/// `.tsv` files use tabs and `.csv` files commas; `.scroll` files use tabs only if the header has a tab and no comma.
+pub fn delimiter_for(extension: &str, source: &str) -> char {
+    match extension {
+        "tsv" => '\t',
+        "csv" => ',',
+        _ => {
+            let header = source.lines().next().unwrap_or_default();
+            if header.contains('\t') && !header.contains(',') { '\t' } else { ',' }
+        },
+    }
+}
+
+
+/// Parses a table into rows of fields, checking every row is as wide as the header
+///
+/// # Props
+/// - `source`: The contents of the file
+/// - `delimiter`: The field separator, usually `,` or `\t`
+///
+/// # Returns
+/// - `Ok` with the header row first
+/// - `Err(e)` pointing at an unterminated quote, stray text after a closing quote or a row of the wrong width
+pub fn parse(source: &str, delimiter: char) -> Result<Vec<Vec<String>>, CsvError> {
+    Ok(parse_lines(source, delimiter)?.into_iter().map(|(_, row)| row).collect())
+}
+
+
+/// Same as [`parse`], keeping the line each row starts on so later checks can point into the file
+pub fn parse_lines(source: &str, delimiter: char) -> Result<Vec<(usize, Vec<String>)>, CsvError> {
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    let mut chars = source.chars().peekable();
+    let (mut line, mut column) = (1, 1);
+
+    let mut rows: Vec<(usize, Vec<String>)> = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut row_line = 1;
+    let mut field = String::new();
+    // Whether the current field was quoted, so surrounding spaces of the quotes are not kept
+    let mut quoted = false;
+    let mut blank_line = true;
+
+    let is_space = |c: char| c != delimiter && (c == ' ' || c == '\t');
+
+    loop {
+        let c = chars.next();
+        match c {
+            Some('"') if !quoted && field.trim().is_empty() => {
+                let (quote_line, quote_column) = (line, column);
+                field.clear();
+                quoted = true;
+                blank_line = false;
+                column += 1;
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                            column += 2;
+                        },
+                        Some('"') => {
+                            column += 1;
+                            break;
+                        },
+                        Some('\n') => {
+                            field.push('\n');
+                            line += 1;
+                            column = 1;
+                        },
+                        Some(c) => {
+                            field.push(c);
+                            column += 1;
+                        },
+                        None => return Err(CsvError {
+                            line: quote_line,
+                            column: quote_column,
+                            message: "This quote is never closed".to_string(),
+                        }),
+                    }
+                }
+                while chars.peek().is_some_and(|c| is_space(*c)) {
+                    chars.next();
+                    column += 1;
+                }
+                match chars.peek() {
+                    None | Some('\n') | Some('\r') => (),
+                    Some(c) if *c == delimiter => (),
+                    Some(_) => return Err(CsvError {
+                        line,
+                        column,
+                        message: "Expected a delimiter after the closing quote; quotes inside a field are written as \"\"".to_string(),
+                    }),
+                }
+            },
+            Some(c) if c == delimiter => {
+                row.push(finish(&mut field, quoted));
+                quoted = false;
+                blank_line = false;
+                column += 1;
+            },
+            Some('\r') | Some('\n') | None => {
+                if c == Some('\r') && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                if blank_line && field.trim().is_empty() {
+                    field.clear();
+                } else {
+                    row.push(finish(&mut field, quoted));
+                    if let Some((_, header)) = rows.first() {
+                        if header.len() != row.len() {
+                            return Err(CsvError {
+                                line: row_line,
+                                column: 1,
+                                message: format!("This row has {} fields but the header has {}", row.len(), header.len()),
+                            });
+                        }
+                    }
+                    rows.push((row_line, std::mem::take(&mut row)));
+                }
+                quoted = false;
+                blank_line = true;
+                line += 1;
+                column = 1;
+                row_line = line;
+                if c.is_none() {
+                    break;
+                }
+            },
+            Some(c) => {
+                field.push(c);
+                if !is_space(c) {
+                    blank_line = false;
+                }
+                column += 1;
+            },
+        }
+    }
+    Ok(rows)
+}
+
+
+fn finish(field: &mut String, quoted: bool) -> String {
+    let value = std::mem::take(field);
+    match quoted {
+        true => value,
+        false => value.trim().to_string(),
+    }
+}
+
+
+/// Writes rows as a table that [`parse`] reads back unchanged
+///
+/// Fields are quoted when they contain the delimiter, a quote or a line break, or start or end with whitespace.
+///
+/// # Props
+/// - `rows`: The rows to write, header first
+/// - `separator`: Written between fields; its first character is the delimiter, e.g. `", "` or `"\t"`
+pub fn write(rows: &[Vec<String>], separator: &str) -> String {
+    let delimiter = separator.chars().next().unwrap_or(',');
+    let escape = |field: &String| -> String {
+        let needs_quotes = field.contains(delimiter)
+            || field.contains(['"', '\n', '\r'])
+            || field.trim() != field;
+        match needs_quotes {
+            true => format!("\"{}\"", field.replace('"', "\"\"")),
+            false => field.clone(),
+        }
+    };
+
+    rows.iter()
+        .map(|row| row.iter().map(escape).collect::<Vec<String>>().join(separator) + "\n")
+        .collect()
+}
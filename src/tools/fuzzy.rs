@@ -0,0 +1,72 @@
+/// Scores how well a query matches a text, with the query's characters appearing in order
+///
+/// Matches at word starts and runs of consecutive characters score higher, gaps score lower, and a
+/// query found as a whole substring gets a bonus. Case and spaces in the query are ignored.
+///
+/// # Returns
+/// - `Some(score)` if every character of the query appears in the text, in order
+/// - `None` otherwise
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let lowered = text.to_lowercase();
+    let text: Vec<char> = lowered.chars().collect();
+
+    let mut score: i64 = 0;
+    let mut matched = 0;
+    let mut last: Option<usize> = None;
+    for (i, c) in text.iter().enumerate() {
+        if matched == query.len() {
+            break;
+        }
+        if *c != query[matched] {
+            continue;
+        }
+        score += 1;
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            score += 8;
+        }
+        match last {
+            Some(l) if l + 1 == i => score += 5,
+            Some(l) => score -= (i - l - 1).min(5) as i64,
+            None => (),
+        }
+        last = Some(i);
+        matched += 1;
+    }
+    if matched < query.len() {
+        return None;
+    }
+
+    let whole: String = query.iter().collect();
+    if lowered.contains(&whole) {
+        score += 20;
+    }
+    // Among equal matches, prefer shorter texts
+    Some(score - text.len() as i64 / 10)
+}
+
+
+/// Ranks rows by their best matching column
+///
+/// # Props
+/// - `query`: The text typed by the user
+/// - `rows`: The rows to search, e.g. the rows of a sys database without its header
+///
+/// # Returns
+/// The indices of matching rows, best match first; ties keep their original order
+pub fn rank(query: &str, rows: &[Vec<String>]) -> Vec<usize> {
+    let mut scored: Vec<(usize, i64)> = rows.iter()
+        .enumerate()
+        .filter_map(|(i, row)| {
+            row.iter()
+                .filter_map(|cell| score(query, cell))
+                .max()
+                .map(|best| (i, best))
+        })
+        .collect();
+    scored.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(i, _)| i).collect()
+}
@@ -0,0 +1,152 @@
+use crate::{
+    prelude::*,
+    tools::workspace,
+    types::files::load_attributes,
+};
+use std::fs::write;
+
+
+
+/// An entry whose `One` or `Multi` attribute holds a value of a sys database
+#[derive(Clone, Debug, PartialEq)]
+pub struct Usage {
+    /// The directory of the entry, relative to `DOC_DIR`
+    pub dir: String,
+    pub entry: String,
+    pub attribute: String,
+}
+
+
+/// The attributes of a directory that take their options from `list_id`
+///
+/// # Returns
+/// - `Ok` with the linked attributes, possibly none
+/// - `Err(e)` if the directory's schema cannot be read, so it is unknown whether it uses the table
+fn linked_attributes(dir: &PathBuf, list_id: &str) -> Result<AttributeVec> {
+    let attributes = load_attributes(dir, &mut Vec::new())
+        .map_err(|e| Report::msg(format!("Attributes could not be read, so the rename was stopped. {e}")))?;
+    Ok(attributes.into_iter()
+        .filter(|(_, field, _)| match field {
+            InputField::One { id, .. } | InputField::Multi { id, .. } => id == list_id,
+            _ => false,
+        })
+        .collect())
+}
+
+
+/// Finds every entry in the workspace that uses a value of a sys database
+///
+/// # Props
+/// - `list_id`: The name of the database in `DOC_DIR/sys`
+/// - `value`: The key of the row, i.e. its first column
+///
+/// # Returns
+/// The entries sorted by directory and name, and the directories skipped because their schema or database
+/// cannot be read
+pub fn usages(list_id: &str, value: &str) -> (Vec<Usage>, Vec<String>) {
+    let results: Vec<Result<Vec<Usage>, String>> = workspace::database_dirs()
+        .par_iter()
+        .map(|dir| {
+            let dir_name = workspace::relative_to_docs(dir).unwrap_or(dir.to_string_lossy().into_owned());
+            let attributes = linked_attributes(dir, list_id).map_err(|_| dir_name.clone())?;
+            let objects = match attributes.is_empty() {
+                true => HashMap::new(),
+                false => json_processor::get_json_hashmap(&dir.join(".database.json")).map_err(|_| dir_name.clone())?,
+            };
+
+            Ok(objects.into_iter()
+                .flat_map(|(entry, map)| {
+                    attributes.iter()
+                        .filter(|(title, field, _)| map.get(title)
+                            .is_some_and(|stored| FieldValue::from_json(stored, Some(field)).items().iter().any(|item| item == value)))
+                        .map(|(title, _, _)| Usage { dir: dir_name.clone(), entry: entry.clone(), attribute: title.clone() })
+                        .collect::<Vec<Usage>>()
+                })
+                .collect())
+        })
+        .collect();
+
+    let mut found: Vec<Usage> = Vec::new();
+    let mut skipped: Vec<String> = Vec::new();
+    for result in results {
+        match result {
+            Ok(v) => found.extend(v),
+            Err(dir) => skipped.push(dir),
+        }
+    }
+    found.sort_by(|a, b| (&a.dir, &a.entry, &a.attribute).cmp(&(&b.dir, &b.entry, &b.attribute)));
+    skipped.sort();
+    (found, skipped)
+}
+
+
+/// Prepares replacing a value of a sys database in every entry of the workspace that uses it
+///
+/// Every database is read before anything is written, so a directory that cannot be read stops the
+/// rename while the workspace is still untouched. `Multi` selections that already contain the new value
+/// keep a single copy of it.
+///
+/// # Returns
+/// - `Ok` with each `.database.json` to rewrite and its new contents
+/// - `Err(e)` if a database that may use the value cannot be read
+pub fn plan_rename(list_id: &str, old: &str, new: &str) -> Result<Vec<(PathBuf, String)>> {
+    let mut rewrites = Vec::new();
+    for dir in workspace::database_dirs() {
+        let attributes = linked_attributes(&dir, list_id)?;
+        if attributes.is_empty() {
+            continue;
+        }
+        let db_path = dir.join(".database.json");
+        let mut objects = json_processor::get_json_hashmap(&db_path)
+            .map_err(|e| Report::msg(format!("{} could not be read. {e}", db_path.display())))?;
+        let mut dir_changed = false;
+
+        for map in objects.values_mut() {
+            for (title, field, _) in attributes.iter() {
+                let stored = match map.get_mut(title) {
+                    Some(v) => v,
+                    None => continue,
+                };
+                let items = FieldValue::from_json(stored, Some(field)).items();
+                if !items.iter().any(|item| item == old) {
+                    continue;
+                }
+                let mut renamed: Vec<String> = Vec::new();
+                for item in items {
+                    let item = if item == old { new.to_string() } else { item };
+                    if !renamed.contains(&item) {
+                        renamed.push(item);
+                    }
+                }
+                *stored = match field {
+                    InputField::Multi { .. } => FieldValue::List(renamed),
+                    _ => FieldValue::Text(renamed.join(", ")),
+                }.to_json();
+                dir_changed = true;
+            }
+        }
+
+        if dir_changed {
+            let metadata = json_processor::hashmap_to_vec(&objects);
+            let json_array = json_processor::vec_to_json(&metadata);
+            rewrites.push((db_path, serde_json::to_string_pretty(&json_array)?));
+        }
+    }
+    Ok(rewrites)
+}
+
+
+/// Writes the databases prepared by [`plan_rename`], carrying on past files that fail
+///
+/// # Returns
+/// - `Ok` if every file was written
+/// - `Err(e)` listing the files that still hold the old value
+pub fn apply_rename(rewrites: &[(PathBuf, String)]) -> Result<()> {
+    let failed: Vec<String> = rewrites.iter()
+        .filter_map(|(path, contents)| write(path, contents).err().map(|e| format!("{} ({e})", path.display())))
+        .collect();
+    match failed.is_empty() {
+        true => Ok(()),
+        false => Err(Report::msg(format!("These databases still use the old value: {}", failed.join(", ")))),
+    }
+}
@@ -9,7 +9,12 @@ use std::{
 
 
 
-pub fn get_json_hashmap(db_path: &PathBuf) -> Result<HashMap<String, HashMap<String, String>>> {
+/// Reads a `.database.json` into its records, keyed by `__ID`
+///
+/// Values are kept as JSON so numbers, booleans and lists survive a round trip; see [`FieldValue::from_json`].
+///
+/// [`FieldValue::from_json`]: crate::types::value::FieldValue::from_json
+pub fn get_json_hashmap(db_path: &PathBuf) -> Result<HashMap<String, HashMap<String, Value>>> {
         let data = read_to_string(db_path)?;
         let parsed_binding: Value = serde_json::from_str(&data)?;
         match parsed_binding {
@@ -20,25 +25,20 @@ pub fn get_json_hashmap(db_path: &PathBuf) -> Result<HashMap<String, HashMap<Str
                         match file_struct {
                             // For each file object...
                             Value::Object(map) => {
-                                // "Correct" the map by removing quotation marks and fixing type
-                                let corrected_map = map.iter()
-                                    .map(|(k, v)| {
-                                        let k_trimmed = k.trim_matches('"');
-                                        let v_string = v.to_string();
-                                        let v_trimmed = v_string.trim_matches('"');
-                                        (k_trimmed.to_string(), v_trimmed.to_string())
-                                    })
-                                    .collect::<HashMap<String, String>>();
-                                let id = corrected_map
+                                let record = map.clone()
+                                    .into_iter()
+                                    .collect::<HashMap<String, Value>>();
+                                let id = record
                                     .get("__ID")
-                                    .cloned()
-                                    .unwrap_or("".to_string());
-                                Ok((id, corrected_map))
+                                    .and_then(|id| id.as_str())
+                                    .unwrap_or("")
+                                    .to_string();
+                                Ok((id, record))
                             },
                             _ => Err(Report::msg("JSON parsing failed"))
                         }
                     })
-                    .collect::<Result<HashMap<String, HashMap<String, String>>>>()?;
+                    .collect::<Result<HashMap<String, HashMap<String, Value>>>>()?;
                 Ok(result)
             },
             _ => Err(Report::msg("JSON parsing failed"))
@@ -46,7 +46,7 @@ pub fn get_json_hashmap(db_path: &PathBuf) -> Result<HashMap<String, HashMap<Str
 }
 
 
-pub fn hashmap_to_vec(json: &HashMap<String, HashMap<String, String>>) -> Vec<Vec<(String, String)>> {
+pub fn hashmap_to_vec(json: &HashMap<String, HashMap<String, Value>>) -> Vec<Vec<(String, Value)>> {
     json 
         .into_iter()
         .map(|(_, M)| {
@@ -58,11 +58,11 @@ pub fn hashmap_to_vec(json: &HashMap<String, HashMap<String, String>>) -> Vec<Ve
 }
 
 
-pub fn vec_to_json(vector: &Vec<Vec<(String, String)>>) -> Vec<Value> {
+pub fn vec_to_json(vector: &Vec<Vec<(String, Value)>>) -> Vec<Value> {
     let mut result = vector.into_iter().map(|vec| {
         let mut map = Map::new();
         for (K, V) in vec {
-            map.insert(K.clone(), Value::String(V.clone()));
+            map.insert(K.clone(), V.clone());
         }
         Value::Object(map)
     }).collect();
@@ -71,13 +71,13 @@ pub fn vec_to_json(vector: &Vec<Vec<(String, String)>>) -> Vec<Value> {
 }
 
 
-pub fn update_json_hashmap(map: &mut HashMap<String, HashMap<String, String>>, name: &str, contents: Vec<(String, String)>) {
-    let content_map: HashMap<String, String> = contents.into_iter().collect();
+pub fn update_json_hashmap(map: &mut HashMap<String, HashMap<String, Value>>, name: &str, contents: Vec<(String, Value)>) {
+    let content_map: HashMap<String, Value> = contents.into_iter().collect();
     map.insert(name.to_string(), content_map);
 }
 
 
-pub fn delete_from_hashmap(map: &mut HashMap<String, HashMap<String, String>>, name: &str) {
+pub fn delete_from_hashmap(map: &mut HashMap<String, HashMap<String, Value>>, name: &str) {
     map.remove(name);
 }
 
@@ -89,11 +89,11 @@ fn sort_json(vec: &mut Vec<Value>) {
     });
 }
 
-pub fn rename_in_hashmap(map: &mut HashMap<String, HashMap<String, String>>, old_name: &str, new_name: &str) {
+pub fn rename_in_hashmap(map: &mut HashMap<String, HashMap<String, Value>>, old_name: &str, new_name: &str) {
     let item_binding = map.remove(old_name);
     assert!(item_binding.is_some(), "Attempted to rename an item that could not be found");
-    let mut item: HashMap<String, String> = item_binding.unwrap();
-    item.insert("__ID".to_string(), new_name.to_string());
+    let mut item: HashMap<String, Value> = item_binding.unwrap();
+    item.insert("__ID".to_string(), Value::String(new_name.to_string()));
 
     map.insert(new_name.to_string(), item);
 }
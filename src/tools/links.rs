@@ -0,0 +1,58 @@
+static DOI_PREFIXES: [&str; 5] = [
+    "https://doi.org/",
+    "http://doi.org/",
+    "https://dx.doi.org/",
+    "http://dx.doi.org/",
+    "doi:",
+];
+
+
+
+/// Checks if a string is an absolute web address
+///
+/// # Props
+/// - `url`: The string to check
+///
+/// # Returns
+/// - `true` if `url` is an `http(s)` address with a host and no whitespace
+/// - `false` otherwise
+pub fn is_valid_url(url: &str) -> bool {
+    let remainder = match url.strip_prefix("https://").or(url.strip_prefix("http://")) {
+        Some(v) => v,
+        None => return false,
+    };
+    let host = remainder.split(['/', '?', '#']).next().unwrap_or_default();
+    !url.chars().any(char::is_whitespace) &&
+        !host.is_empty() &&
+        (host.contains('.') || host.starts_with("localhost"))
+}
+
+
+/// Converts any common DOI notation to its canonical resolver link
+///
+/// # Props
+/// - `doi`: A bare DOI (`10.1000/xyz`), a `doi:` URI or a `doi.org` link
+///
+/// # Returns
+/// - `Some` with the `https://doi.org/` form if `doi` is well-formed
+/// - `None` otherwise
+pub fn normalize_doi(doi: &str) -> Option<String> {
+    let trimmed = doi.trim();
+    let lowercase = trimmed.to_lowercase();
+    let bare = DOI_PREFIXES.iter()
+        .find(|prefix| lowercase.starts_with(*prefix))
+        .map(|prefix| trimmed[prefix.len()..].trim_start())
+        .unwrap_or(trimmed);
+
+    let (registrant, suffix) = bare.split_once('/')?;
+    let code = registrant.strip_prefix("10.")?;
+    let valid_registrant = !code.is_empty() &&
+        code.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    let valid_suffix = !suffix.is_empty() && !suffix.chars().any(char::is_whitespace);
+
+    if valid_registrant && valid_suffix {
+        Some(format!("https://doi.org/{bare}"))
+    } else {
+        None
+    }
+}
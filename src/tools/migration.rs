@@ -0,0 +1,176 @@
+use crate::{
+    prelude::*,
+    types::value::legacy_options,
+};
+use serde_json::Value;
+use std::fs::write;
+
+
+
+/// Differences between a directory's schema and the records in its `.database.json`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SchemaDiff {
+    /// Attributes that at least one record has no value for
+    pub added: Vec<String>,
+    /// Record fields that no attribute declares anymore
+    pub removed: Vec<String>,
+}
+
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+
+    /// Guesses which added attribute an obsolete field was renamed to
+    ///
+    /// Names are compared ignoring case, spaces and underscores; a lone added/removed pair is always suggested.
+    pub fn rename_suggestion(&self, removed: &str) -> Option<String> {
+        let simplify = |name: &str| name.to_lowercase().replace([' ', '_', '-'], "");
+        if self.added.len() == 1 && self.removed.len() == 1 {
+            return self.added.first().cloned();
+        }
+        self.added.iter()
+            .find(|added| {
+                let (a, r) = (simplify(added), simplify(removed));
+                a.contains(&r) || r.contains(&a)
+            })
+            .cloned()
+    }
+}
+
+
+/// One change applied to every record of a database
+#[derive(Clone, Debug, PartialEq)]
+pub enum MigrationStep {
+    /// Give records missing `attribute` the value `value`
+    Backfill { attribute: String, value: FieldValue },
+    /// Move the value of `from` to `to`, keeping any value `to` already has
+    Rename { from: String, to: String },
+    /// Remove `key` from every record
+    Drop { key: String },
+}
+
+
+
+/// Compares the attributes of a directory with the fields found in its records
+///
+/// # Props
+/// - `objects`: The records of `.database.json`, by `__ID`
+/// - `attributes`: The current schema
+///
+/// # Returns
+/// The attributes missing from some record and the fields no longer declared
+pub fn diff(objects: &HashMap<String, HashMap<String, Value>>, attributes: &AttributeVec) -> SchemaDiff {
+    let declared = |key: &String| key == "__ID" || attributes.iter().any(|(title, _, _)| title == key);
+
+    let added = attributes.iter()
+        .filter(|(title, _, _)| objects.values().any(|map| !map.contains_key(title)))
+        .map(|(title, _, _)| title.clone())
+        .collect();
+    let mut removed: Vec<String> = objects.values()
+        .flat_map(|map| map.keys())
+        .filter(|key| !declared(key))
+        .cloned()
+        .collect();
+    removed.sort();
+    removed.dedup();
+
+    SchemaDiff { added, removed }
+}
+
+
+/// Applies every migration step to a database and writes it back in one go
+pub fn migrate(db_path: &PathBuf, steps: &[MigrationStep]) -> Result<()> {
+    let mut objects = json_processor::get_json_hashmap(db_path)?;
+
+    for map in objects.values_mut() {
+        for step in steps {
+            match step {
+                MigrationStep::Rename { from, to } => {
+                    if let Some(value) = map.remove(from) {
+                        let target = map.entry(to.clone()).or_default();
+                        if FieldValue::from_json(target, None).is_empty() {
+                            *target = value;
+                        }
+                    }
+                },
+                MigrationStep::Drop { key } => {
+                    map.remove(key);
+                },
+                MigrationStep::Backfill { .. } => (),
+            }
+        }
+        // Backfill last, so renamed values take precedence over defaults
+        for step in steps {
+            if let MigrationStep::Backfill { attribute, value } = step {
+                map.entry(attribute.clone()).or_insert(value.to_json());
+            }
+        }
+    }
+
+    let metadata = json_processor::hashmap_to_vec(&objects);
+    let json_array = json_processor::vec_to_json(&metadata);
+    write(db_path, serde_json::to_string_pretty(&json_array)?)?;
+    Ok(())
+}
+
+
+/// Converts every declared value of a database to its typed form, in memory
+///
+/// # Props
+/// - `options`: The [`legacy_options`] of each attribute, in schema order
+///
+/// # Returns
+/// Whether any value was stored in an older, untyped form
+fn type_records(objects: &mut HashMap<String, HashMap<String, Value>>, attributes: &AttributeVec, options: &[Vec<String>]) -> bool {
+    let mut changed = false;
+    for map in objects.values_mut() {
+        for ((title, field, _), options) in attributes.iter().zip(options.iter()) {
+            if let Some(value) = map.get_mut(title) {
+                let typed = FieldValue::from_stored(value, field, options).to_json();
+                if *value != typed {
+                    *value = typed;
+                    changed = true;
+                }
+            }
+        }
+    }
+    changed
+}
+
+
+/// Checks whether a database still holds values written by older versions of Scroll
+///
+/// Nothing is written; the values are read in their typed form either way, and [`upgrade_values`]
+/// rewrites the file once the user confirms.
+///
+/// # Props
+/// - `objects`: The records of `.database.json`, by `__ID`
+/// - `options`: The [`legacy_options`] of each attribute, in schema order
+pub fn needs_upgrade(objects: &HashMap<String, HashMap<String, Value>>, attributes: &AttributeVec, options: &[Vec<String>]) -> bool {
+    type_records(&mut objects.clone(), attributes, options)
+}
+
+
+/// Rewrites values stored by older versions of Scroll in their typed form
+///
+/// Numbers, booleans and list selections used to be written as strings. The file is only written when
+/// something changed.
+///
+/// # Returns
+/// - `Ok(true)` if the database was upgraded
+/// - `Ok(false)` if every value already had its typed form
+/// - `Err(e)` if the database cannot be read or written
+pub fn upgrade_values(db_path: &PathBuf, attributes: &AttributeVec) -> Result<bool> {
+    let mut objects = json_processor::get_json_hashmap(db_path)?;
+    let options: Vec<Vec<String>> = attributes.iter().map(|(_, field, _)| legacy_options(field)).collect();
+    let changed = type_records(&mut objects, attributes, &options);
+    if changed {
+        let metadata = json_processor::hashmap_to_vec(&objects);
+        let json_array = json_processor::vec_to_json(&metadata);
+        write(db_path, serde_json::to_string_pretty(&json_array)?)?;
+    }
+    Ok(changed)
+}
@@ -3,3 +3,17 @@ pub mod scroll_processor;
 pub mod compare;
 pub mod custom_panic;
 pub mod serde;
+pub mod links;
+pub mod time;
+pub mod workspace;
+pub mod auto_fields;
+pub mod schema_parser;
+pub mod migration;
+pub mod csv_parser;
+pub mod integrity;
+pub mod fuzzy;
+pub mod spreadsheet;
+pub mod preferences;
+pub mod query;
+pub mod search;
+pub mod views;
@@ -0,0 +1,123 @@
+use crate::{
+    prelude::*,
+    tools::{compare::EmptyPlacement, workspace},
+    types::files::{Order, SortDirection},
+};
+use homedir::my_home;
+use serde_json::{json, Map, Value};
+use std::{
+    fs::{create_dir_all, read_to_string, write},
+    path::Path,
+};
+
+
+
+/// Per-user settings that should not end up in the shared workspace, e.g. the sort order of each directory
+fn preferences_path() -> Result<PathBuf> {
+    let home = my_home()?.ok_or(Report::msg("No home directory"))?;
+    Ok(home.join(".config/scroll/preferences.json"))
+}
+
+
+fn read_preferences() -> Result<Map<String, Value>> {
+    let path = preferences_path()?;
+    if !path.is_file() {
+        return Ok(Map::new());
+    }
+    match serde_json::from_str(&read_to_string(path)?)? {
+        Value::Object(map) => Ok(map),
+        _ => Err(Report::msg("preferences.json is not an object")),
+    }
+}
+
+
+/// Reads the sort order saved for a directory
+///
+/// # Returns
+/// The saved keys and placement of empty values, or the default ordering (by file name, empty values last)
+/// if none were saved or the file is unreadable
+pub fn load_sorting(dir: &Path) -> Order {
+    let (workspace, key) = match (workspace::identity(), workspace::relative_to_docs(dir)) {
+        (Ok(workspace), Some(key)) => (workspace, key),
+        _ => return Order::default(),
+    };
+    let preferences = read_preferences().unwrap_or_default();
+    let keys = preferences.get("sorting")
+        .and_then(|sorting| sorting.get(&workspace))
+        .and_then(|sorting| sorting.get(&key))
+        .and_then(|keys| keys.as_array())
+        .map(|keys| keys.iter()
+            .filter_map(|entry| {
+                let column = entry.get("column")?.as_str()?.to_string();
+                let direction = match entry.get("direction")?.as_str()? {
+                    "desc" => SortDirection::Decreasing,
+                    _ => SortDirection::Increasing,
+                };
+                Some((column, direction))
+            })
+            .collect())
+        .unwrap_or_default();
+    let empties = preferences.get("empty_values")
+        .and_then(|empties| empties.get(&workspace))
+        .and_then(|empties| empties.get(&key))
+        .and_then(|v| v.as_str());
+    let empties = match empties {
+        Some("first") => EmptyPlacement::First,
+        _ => EmptyPlacement::Last,
+    };
+    Order { keys, empties }
+}
+
+
+/// Saves the sort order of a directory, keyed by the workspace and then its path relative to `DOC_DIR`
+///
+/// Defaults are removed rather than written, so the file only lists directories that were sorted by hand.
+pub fn save_sorting(dir: &Path, order: &Order) -> Result<()> {
+    let workspace = workspace::identity()?;
+    let key = workspace::relative_to_docs(dir)
+        .ok_or(Report::msg(format!("{} is outside the workspace", dir.display())))?;
+    let mut preferences = read_preferences()?;
+    let keys: Vec<Value> = order.keys.iter()
+        .map(|(column, direction)| json!({
+            "column": column,
+            "direction": match direction {
+                SortDirection::Increasing => "asc",
+                SortDirection::Decreasing => "desc",
+            },
+        }))
+        .collect();
+
+    let sorting = workspace_section(&mut preferences, "sorting", &workspace);
+    match keys.is_empty() {
+        true => sorting.remove(&key),
+        false => sorting.insert(key.clone(), Value::Array(keys)),
+    };
+
+    let empties = workspace_section(&mut preferences, "empty_values", &workspace);
+    match order.empties {
+        EmptyPlacement::Last => empties.remove(&key),
+        EmptyPlacement::First => empties.insert(key, Value::String("first".to_string())),
+    };
+
+    let path = preferences_path()?;
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent)?;
+    }
+    write(path, serde_json::to_string_pretty(&Value::Object(preferences))?)?;
+    Ok(())
+}
+
+
+/// The settings of one workspace under a section of the preferences, replacing anything malformed
+fn workspace_section<'a>(preferences: &'a mut Map<String, Value>, section: &str, workspace: &str) -> &'a mut Map<String, Value> {
+    let section = preferences.entry(section).or_insert(Value::Object(Map::new()));
+    if !section.is_object() {
+        *section = Value::Object(Map::new());
+    }
+    let settings = section.as_object_mut().unwrap()
+        .entry(workspace).or_insert(Value::Object(Map::new()));
+    if !settings.is_object() {
+        *settings = Value::Object(Map::new());
+    }
+    settings.as_object_mut().unwrap()
+}
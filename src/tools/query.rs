@@ -0,0 +1,384 @@
+//! Filters for the file table
+//!
+//! ```text
+//! Owner = "Ana" AND Date >= 2025-06-01
+//! (Status = Done OR Status = Failed) AND NOT Tags ~ pilot
+//! plasmid                       # a bare word matches any column containing it
+//! ```
+//!
+//! Operators are `=`, `!=`, `<`, `<=`, `>`, `>=` and `~` (contains). Values are compared by the type
+//! of their attribute, and `=` on a list attribute matches if any of its items is equal.
+use crate::{
+    prelude::*,
+    tools::{compare, time},
+};
+use chrono::{Local, NaiveDate};
+use nom::{
+    Err, IResult, Offset,
+    branch::alt,
+    bytes::complete::{tag, tag_no_case, take_while1},
+    character::complete::{char, multispace0},
+    combinator::{cut, map, not, peek, value},
+    error::{context, ContextError, ErrorKind, ParseError, VerboseError, VerboseErrorKind},
+    sequence::{delimited, preceded, terminated},
+};
+use std::{cmp::Ordering, fmt};
+
+
+
+type Res<'a, T> = IResult<&'a str, T, VerboseError<&'a str>>;
+
+
+/// A problem in a query, located by its character position
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryError {
+    pub column: usize,
+    pub message: String,
+}
+
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Column {}: {}", self.column, self.message)
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Contains,
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Compare { column: String, operator: Operator, value: String },
+    /// A bare word, matched against every column
+    Term(String),
+}
+
+
+/// A filter set from the row under the table headers
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColumnFilter {
+    /// Keeps rows whose value contains the text, ignoring case
+    Contains(String),
+    /// Keeps rows whose value lies between the bounds, both included; an empty bound is open
+    Range { from: String, to: String },
+    /// Keeps rows with any of the options selected
+    Options(Vec<String>),
+}
+
+
+
+/// Parses a query and checks that the columns it names exist
+///
+/// # Props
+/// - `source`: The text of the query bar
+/// - `attributes`: The attributes of the open directory; `Name` also refers to the file name
+///
+/// # Returns
+/// - `Ok` with the parsed query
+/// - `Err(e)` at the first syntax error or unknown column
+pub fn parse(source: &str, attributes: &AttributeVec) -> Result<Query, QueryError> {
+    let error_at = |at: &str, message: String| QueryError {
+        column: source[..source.offset(at)].chars().count() + 1,
+        message,
+    };
+    let (rest, query) = match expression(source) {
+        Ok(v) => v,
+        Err(Err::Error(e)) | Err(Err::Failure(e)) => {
+            let (at, message) = describe(e);
+            return Err(error_at(at.unwrap_or(source), message));
+        },
+        Err(Err::Incomplete(_)) => return Err(error_at(&source[source.len()..], "Unexpected end of the query".to_string())),
+    };
+    let (rest, _) = multispace0::<&str, VerboseError<&str>>(rest).unwrap_or((rest, ""));
+    if !rest.is_empty() {
+        let message = match rest.starts_with(')') {
+            true => "This ')' has no matching '('",
+            false => "Unexpected text, expected AND, OR or the end of the query",
+        };
+        return Err(error_at(rest, message.to_string()));
+    }
+    if let Some(unknown) = query.columns().into_iter().find(|title| column(attributes, title).is_none()) {
+        let at = source.find(unknown.as_str()).map(|i| &source[i..]).unwrap_or(source);
+        return Err(error_at(at, format!("No attribute is called \"{unknown}\"")));
+    }
+    Ok(query)
+}
+
+
+/// Finds a column of the metadata by attribute title, ignoring case
+///
+/// # Returns
+/// The index in a metadata row (0 being the file name) and the attribute type, if any
+pub fn column<'a>(attributes: &'a AttributeVec, title: &str) -> Option<(usize, Option<&'a InputField>)> {
+    if title == "__ID" {
+        return Some((0, None));
+    }
+    attributes.iter()
+        .position(|(name, _, _)| name.eq_ignore_ascii_case(title.trim()))
+        .map(|i| (i + 1, Some(&attributes[i].1)))
+        .or(match title.trim().eq_ignore_ascii_case("name") {
+            true => Some((0, None)),
+            false => None,
+        })
+}
+
+
+fn describe(error: VerboseError<&str>) -> (Option<&str>, String) {
+    let at = error.errors.first().map(|(at, _)| *at);
+    let message = error.errors.iter()
+        .find_map(|(_, kind)| match kind {
+            VerboseErrorKind::Context(message) => Some(message.to_string()),
+            _ => None,
+        })
+        .unwrap_or("Unexpected input".to_string());
+    (at, message)
+}
+
+
+fn expression(input: &str) -> Res<'_, Query> {
+    let (mut input, mut left) = conjunction(input)?;
+    while let Ok((rest, _)) = keyword("OR")(input) {
+        let (rest, right) = cut(context("Expected a condition after OR", conjunction))(rest)?;
+        left = Query::Or(Box::new(left), Box::new(right));
+        input = rest;
+    }
+    Ok((input, left))
+}
+
+
+/// Conditions joined by AND, or simply written one after the other
+fn conjunction(input: &str) -> Res<'_, Query> {
+    let (mut input, mut left) = negation(input)?;
+    loop {
+        let (rest, _) = multispace0(input)?;
+        if rest.is_empty() || rest.starts_with(')') || keyword("OR")(rest).is_ok() {
+            break;
+        }
+        let rest = keyword("AND")(rest).map(|(rest, _)| rest).unwrap_or(rest);
+        let (rest, right) = cut(context("Expected a condition", negation))(rest)?;
+        left = Query::And(Box::new(left), Box::new(right));
+        input = rest;
+    }
+    Ok((input, left))
+}
+
+
+fn negation(input: &str) -> Res<'_, Query> {
+    let (input, _) = multispace0(input)?;
+    if let Ok((rest, _)) = keyword("NOT")(input) {
+        let (rest, inner) = cut(context("Expected a condition after NOT", negation))(rest)?;
+        return Ok((rest, Query::Not(Box::new(inner))));
+    }
+    if input.starts_with('(') {
+        return delimited(
+            char('('),
+            expression,
+            preceded(multispace0, cut(context("This '(' is never closed", char(')')))),
+        )(input);
+    }
+    condition(input)
+}
+
+
+/// A comparison, or a bare word when no operator follows
+fn condition(input: &str) -> Res<'_, Query> {
+    let (input, first) = context("Expected a condition", word_or_quoted)(input)?;
+    let (rest, _) = multispace0(input)?;
+    match operator(rest) {
+        Ok((rest, operator)) => {
+            let (rest, value) = preceded(multispace0, cut(context("Expected a value after the operator", word_or_quoted)))(rest)?;
+            Ok((rest, Query::Compare { column: first, operator, value }))
+        },
+        Err(_) => Ok((input, Query::Term(first))),
+    }
+}
+
+
+fn operator(input: &str) -> Res<'_, Operator> {
+    alt((
+        value(Operator::NotEqual, tag("!=")),
+        value(Operator::GreaterEqual, tag(">=")),
+        value(Operator::LessEqual, tag("<=")),
+        value(Operator::Equal, tag("=")),
+        value(Operator::Greater, tag(">")),
+        value(Operator::Less, tag("<")),
+        value(Operator::Contains, tag("~")),
+    ))(input)
+}
+
+
+/// Matches a keyword, in any case, that is not the start of a longer word
+fn keyword<'a>(word: &'static str) -> impl FnMut(&'a str) -> Res<'a, &'a str> {
+    preceded(multispace0, terminated(tag_no_case(word), not(peek(take_while1(is_word_char)))))
+}
+
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !"()=!<>~\"".contains(c)
+}
+
+
+fn word_or_quoted(input: &str) -> Res<'_, String> {
+    alt((
+        quoted,
+        map(take_while1(is_word_char), |word: &str| word.to_string()),
+    ))(input)
+}
+
+
+/// Parses a double-quoted value, where `\"` stands for a quote
+fn quoted(input: &str) -> Res<'_, String> {
+    let (mut rest, _) = char('"')(input)?;
+    let mut value = String::new();
+    loop {
+        let mut chars = rest.chars();
+        match chars.next() {
+            Some('"') => return Ok((chars.as_str(), value)),
+            Some('\\') if chars.as_str().starts_with('"') => {
+                value.push('"');
+                rest = &chars.as_str()[1..];
+            },
+            None => {
+                return Err(Err::Failure(VerboseError::add_context(
+                    input, "Unterminated quote", VerboseError::from_error_kind(input, ErrorKind::Char))));
+            },
+            Some(c) => {
+                value.push(c);
+                rest = chars.as_str();
+            },
+        }
+    }
+}
+
+
+
+impl Query {
+    /// Titles of every column named in the query
+    fn columns(&self) -> Vec<String> {
+        match self {
+            Query::And(a, b) | Query::Or(a, b) => [a.columns(), b.columns()].concat(),
+            Query::Not(inner) => inner.columns(),
+            Query::Compare { column, .. } => vec![column.clone()],
+            Query::Term(_) => Vec::new(),
+        }
+    }
+
+
+    /// Checks a metadata row, whose first value is the file name
+    pub fn matches(&self, row: &[FieldValue], attributes: &AttributeVec) -> bool {
+        match self {
+            Query::And(a, b) => a.matches(row, attributes) && b.matches(row, attributes),
+            Query::Or(a, b) => a.matches(row, attributes) || b.matches(row, attributes),
+            Query::Not(inner) => !inner.matches(row, attributes),
+            Query::Term(text) => row.iter().any(|cell| contains(cell, text)),
+            Query::Compare { column: title, operator, value } => {
+                let (index, field) = match column(attributes, title) {
+                    Some(v) => v,
+                    None => return false,
+                };
+                let cell = match row.get(index) {
+                    Some(v) => v,
+                    None => return false,
+                };
+                match operator {
+                    Operator::Contains => contains(cell, value),
+                    Operator::Equal => equals(cell, field, value),
+                    Operator::NotEqual => !equals(cell, field, value),
+                    ordering => match compare_literal(cell, field, value) {
+                        None => false,
+                        Some(o) => match ordering {
+                            Operator::Less => o.is_lt(),
+                            Operator::LessEqual => o.is_le(),
+                            Operator::Greater => o.is_gt(),
+                            _ => o.is_ge(),
+                        },
+                    },
+                }
+            },
+        }
+    }
+}
+
+
+impl ColumnFilter {
+    /// Whether a filter set to these values would keep every row
+    pub fn is_empty(&self) -> bool {
+        match self {
+            ColumnFilter::Contains(text) => text.trim().is_empty(),
+            ColumnFilter::Range { from, to } => from.trim().is_empty() && to.trim().is_empty(),
+            ColumnFilter::Options(options) => options.is_empty(),
+        }
+    }
+
+
+    pub fn matches(&self, cell: &FieldValue, field: Option<&InputField>) -> bool {
+        match self {
+            ColumnFilter::Contains(text) => contains(cell, text),
+            ColumnFilter::Range { from, to } => {
+                let above = from.trim().is_empty() || compare_literal(cell, field, from).is_some_and(|o| o.is_ge());
+                let below = to.trim().is_empty() || compare_literal(cell, field, to).is_some_and(|o| o.is_le());
+                above && below
+            },
+            ColumnFilter::Options(options) => {
+                let items = match cell {
+                    FieldValue::Bool(v) => vec![v.to_string()],
+                    other => other.items(),
+                };
+                options.is_empty() || items.iter().any(|item| options.iter().any(|option| option.eq_ignore_ascii_case(item)))
+            },
+        }
+    }
+}
+
+
+fn contains(cell: &FieldValue, text: &str) -> bool {
+    cell.to_string().to_lowercase().contains(&text.trim().to_lowercase())
+}
+
+
+fn equals(cell: &FieldValue, field: Option<&InputField>, text: &str) -> bool {
+    match cell {
+        FieldValue::List(items) => items.iter().any(|item| item.eq_ignore_ascii_case(text.trim())),
+        FieldValue::Text(v) if v.eq_ignore_ascii_case(text.trim()) => true,
+        _ => compare_literal(cell, field, text) == Some(Ordering::Equal),
+    }
+}
+
+
+/// Compares a stored value with a value typed in a filter, read as the attribute's type
+///
+/// Date-time attributes can be compared with plain dates, e.g. `Modified >= 2025-06-01`. The date is the
+/// day in local time, whatever offset the value was stored with.
+///
+/// # Returns
+/// `None` if the stored value is empty, so it never matches a comparison
+fn compare_literal(cell: &FieldValue, field: Option<&InputField>, text: &str) -> Option<Ordering> {
+    if cell.is_empty() {
+        return None;
+    }
+    let text = text.trim();
+    match field {
+        None => Some(compare::natural(&cell.to_string(), text)),
+        Some(InputField::DateTime { .. } | InputField::Created | InputField::Modified) => {
+            let stored = time::parse_datetime(&cell.to_string())?;
+            match NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+                Ok(date) => Some(stored.with_timezone(&Local).date_naive().cmp(&date)),
+                Err(_) => Some(stored.cmp(&time::parse_datetime(text)?)),
+            }
+        },
+        Some(field) => Some(compare::compare_values(cell, &FieldValue::from_input(field, text), Some(field))),
+    }
+}
@@ -0,0 +1,474 @@
+//! Grammar of `.attributes.scroll` files
+//!
+//! ```text
+//! # Comments start with a hash, on their own line or after a declaration
+//! @extends ..
+//! -Inherited attribute to drop
+//! Name: *Type(arguments) = "default" [placeholder="...", help="..."]
+//! "Quoted: names may contain colons": String(
+//!     pattern="^[A-Z]{2}\d{4}$",
+//!     message="Use two letters and four digits"
+//! )
+//! Buffers: Choices(Tris, pH 8|EDTA|PBS) = "Tris, pH 8|PBS"   # list defaults are separated by '|'
+//! ```
+use crate::{
+    prelude::*,
+    tools::{auto_fields, workspace},
+};
+use nom::{
+    Err, IResult, Offset,
+    branch::alt,
+    bytes::complete::{tag, take_till, take_till1},
+    character::complete::{alpha1, char, digit1, line_ending, multispace0, not_line_ending, space0, space1},
+    combinator::{cut, eof, map, opt, recognize, value, verify},
+    error::{context, ContextError, ErrorKind, ParseError, VerboseError, VerboseErrorKind},
+    multi::separated_list0,
+    sequence::{delimited, pair, preceded, terminated, tuple},
+};
+use regex::Regex;
+use std::{fmt, path::Path};
+
+
+
+type Res<'a, T> = IResult<&'a str, T, VerboseError<&'a str>>;
+
+
+/// A problem in an attribute file, located by line and column
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaError {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    /// The offending line, followed by a caret under the offending column
+    pub snippet: String,
+}
+
+
+impl SchemaError {
+    /// Locates a byte offset of `source` and captures the surrounding line
+    pub fn at(file: &Path, source: &str, offset: usize, message: String) -> Self {
+        let offset = offset.min(source.len());
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line = before.matches('\n').count() + 1;
+        let column = before[line_start..].chars().count() + 1;
+        let text = source[line_start..].lines().next().unwrap_or_default().replace('\t', " ");
+        SchemaError {
+            file: file.to_path_buf(),
+            line,
+            column,
+            message,
+            snippet: format!("{text}\n{}^", " ".repeat(column - 1)),
+        }
+    }
+}
+
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}: {}", self.file.display(), self.line, self.column, self.message)
+    }
+}
+
+
+
+/// One meaningful line (or multi-line declaration) of an attribute file
+#[derive(Clone, Debug)]
+pub enum Line<'a> {
+    /// `@extends <dir>`: start from the schema of another directory, relative to this one
+    Extends { dir: &'a str },
+    /// `-Name`: drop an inherited attribute
+    Remove { title: String, at: &'a str },
+    Declaration { title: String, field: InputField, info: AttributeInfo },
+}
+
+
+/// A `key=value` pair inside parentheses or brackets
+#[derive(Clone, Debug)]
+struct OptionPair<'a> {
+    key: &'a str,
+    value: String,
+    value_at: &'a str,
+}
+
+
+/// Arguments following an attribute type
+#[derive(Clone, Debug)]
+enum Arguments<'a> {
+    Options(Vec<OptionPair<'a>>),
+    Raw(&'a str),
+}
+
+
+/// A line as written, before its type is checked
+#[derive(Clone, Debug)]
+enum RawLine<'a> {
+    Extends { dir: &'a str },
+    Remove { title: String, at: &'a str },
+    Declaration {
+        title: String,
+        required: bool,
+        kind: &'a str,
+        arguments: Option<Arguments<'a>>,
+        default: Option<String>,
+        details: Vec<OptionPair<'a>>,
+    },
+}
+
+
+
+/// Parses a whole attribute file
+///
+/// # Props
+/// - `source`: The contents of `.attributes.scroll`
+///
+/// # Returns
+/// - `Ok` with every directive and declaration, in file order
+/// - `Err((offset, message))` at the first syntax or type error
+pub fn parse_schema(source: &str) -> Result<Vec<Line<'_>>, (usize, String)> {
+    let mut lines = Vec::new();
+    let mut rest = source;
+    let mut extends_seen = false;
+
+    loop {
+        rest = skip_blank(rest);
+        if rest.is_empty() {
+            break;
+        }
+        let start = rest;
+        let parsed = match line(rest) {
+            Ok((remaining, parsed)) => {
+                rest = remaining;
+                parsed
+            },
+            Err(Err::Error(e)) | Err(Err::Failure(e)) => return Err(locate(source, e)),
+            Err(Err::Incomplete(_)) => return Err((source.offset(rest), "Unexpected end of file".to_string())),
+        };
+
+        let checked = match parsed {
+            RawLine::Extends { dir } => {
+                if extends_seen {
+                    return Err((source.offset(start), "A schema can only extend one directory".to_string()));
+                }
+                extends_seen = true;
+                Line::Extends { dir }
+            },
+            RawLine::Remove { title, at } => Line::Remove { title, at },
+            RawLine::Declaration { title, required, kind, arguments, default, details } => {
+                let field = build_field(kind, required, arguments)
+                    .map_err(|(at, message)| (source.offset(at), message))?;
+                let info = build_info(&field, default, details)
+                    .map_err(|(at, message)| (source.offset(at.unwrap_or(kind)), message))?;
+                Line::Declaration { title, field, info }
+            },
+        };
+        lines.push(checked);
+    }
+    Ok(lines)
+}
+
+
+/// Skips blank lines and comment lines
+fn skip_blank(mut input: &str) -> &str {
+    loop {
+        let result: Res<&str> = recognize(tuple((space0, opt(comment), line_ending)))(input);
+        match result {
+            Ok((rest, _)) => input = rest,
+            Err(_) => {
+                let trailing: Res<&str> = recognize(tuple((space0, opt(comment), eof)))(input);
+                return match trailing {
+                    Ok((rest, _)) => rest,
+                    Err(_) => input,
+                };
+            },
+        }
+    }
+}
+
+
+/// Converts the innermost parser error to an offset and the closest descriptive message
+fn locate(source: &str, error: VerboseError<&str>) -> (usize, String) {
+    let offset = error.errors.first()
+        .map(|(at, _)| source.offset(at))
+        .unwrap_or(0);
+    let message = error.errors.iter()
+        .find_map(|(_, kind)| match kind {
+            VerboseErrorKind::Context(message) => Some(message.to_string()),
+            _ => None,
+        })
+        .or(error.errors.first().map(|(_, kind)| match kind {
+            VerboseErrorKind::Char(c) => format!("Expected '{c}'"),
+            _ => "Unexpected input".to_string(),
+        }))
+        .unwrap_or("Unexpected input".to_string());
+    (offset, message)
+}
+
+
+fn line(input: &str) -> Res<'_, RawLine<'_>> {
+    let (input, _) = space0(input)?;
+    if input.starts_with("@extends") {
+        return extends(input);
+    }
+    if input.starts_with('@') {
+        return Err(Err::Failure(VerboseError::add_context(
+            input, "Unknown directive, expected @extends", VerboseError::from_error_kind(input, ErrorKind::Tag))));
+    }
+    if input.starts_with('-') {
+        return remove(input);
+    }
+    declaration(input)
+}
+
+
+fn extends(input: &str) -> Res<'_, RawLine<'_>> {
+    let (input, dir) = preceded(
+        pair(tag("@extends"), space1),
+        cut(context("Expected a directory to extend", take_till1(|c| c == '#' || c == '\n' || c == '\r'))),
+    )(input)?;
+    let (input, _) = end_of_line(input)?;
+    Ok((input, RawLine::Extends { dir: dir.trim() }))
+}
+
+
+fn remove(input: &str) -> Res<'_, RawLine<'_>> {
+    let (input, _) = pair(char('-'), space0)(input)?;
+    let at = input;
+    let (input, title) = cut(context("Expected the name of the attribute to remove", attribute_name))(input)?;
+    let (input, _) = end_of_line(input)?;
+    Ok((input, RawLine::Remove { title, at }))
+}
+
+
+fn declaration(input: &str) -> Res<'_, RawLine<'_>> {
+    let (input, title) = context("Expected an attribute name", attribute_name)(input)?;
+    let (input, _) = tuple((space0, cut(context("Expected ':' after the attribute name", char(':'))), space0))(input)?;
+    let (input, required) = map(opt(char('*')), |asterisk| asterisk.is_some())(input)?;
+    let (input, kind) = cut(context("Expected an attribute type", alpha1))(input)?;
+    let (input, arguments) = opt(|i| arguments(kind, i))(input)?;
+    let (input, default) = opt(preceded(
+        tuple((space0, char('='), space0)),
+        cut(context("Expected a quoted default value", quoted_value)),
+    ))(input)?;
+    let (input, details) = opt(preceded(space0, |i| option_list('[', ']', i)))(input)?;
+    let (input, _) = end_of_line(input)?;
+    Ok((input, RawLine::Declaration {
+        title,
+        required,
+        kind,
+        arguments,
+        default,
+        details: details.unwrap_or_default(),
+    }))
+}
+
+
+fn end_of_line(input: &str) -> Res<'_, ()> {
+    cut(context(
+        "Unexpected text, expected the end of the line",
+        value((), tuple((space0, opt(comment), alt((line_ending, eof))))),
+    ))(input)
+}
+
+
+fn comment(input: &str) -> Res<'_, &str> {
+    preceded(char('#'), not_line_ending)(input)
+}
+
+
+/// Parses a quoted name, or a bare name running up to the colon
+fn attribute_name(input: &str) -> Res<'_, String> {
+    alt((
+        quoted_value,
+        map(
+            verify(
+                take_till1(|c| c == ':' || c == '#' || c == '\n' || c == '\r'),
+                |name: &str| !name.trim().is_empty() && !name.starts_with('"'),
+            ),
+            |name: &str| name.trim().to_string(),
+        ),
+    ))(input)
+}
+
+
+/// Parses a double-quoted value, where only `\"` is treated as an escape so regex escapes pass through
+fn quoted_value(input: &str) -> Res<'_, String> {
+    let (mut rest, _) = char('"')(input)?;
+    let mut value = String::new();
+    loop {
+        let mut chars = rest.chars();
+        match chars.next() {
+            Some('"') => return Ok((chars.as_str(), value)),
+            Some('\\') if chars.as_str().starts_with('"') => {
+                value.push('"');
+                rest = &chars.as_str()[1..];
+            },
+            Some('\n') | None => {
+                return Err(Err::Failure(VerboseError::add_context(
+                    input, "Unterminated quote", VerboseError::from_error_kind(input, ErrorKind::Char))));
+            },
+            Some(c) => {
+                value.push(c);
+                rest = chars.as_str();
+            },
+        }
+    }
+}
+
+
+/// Parses the arguments of a type; `String` takes options, every other type takes raw text
+fn arguments<'a>(kind: &str, input: &'a str) -> Res<'a, Arguments<'a>> {
+    if kind == "String" {
+        return map(|i| option_list('(', ')', i), Arguments::Options)(input);
+    }
+    let (rest, raw) = preceded(char('('), take_till(|c| c == ')'))(input)?;
+    match char::<&str, VerboseError<&str>>(')')(rest) {
+        Ok((rest, _)) => Ok((rest, Arguments::Raw(raw))),
+        Err(_) => Err(Err::Failure(VerboseError::add_context(
+            input, "This '(' is never closed", VerboseError::from_error_kind(input, ErrorKind::Char)))),
+    }
+}
+
+
+/// Parses a delimited list of `key=value` options, which may span several lines
+fn option_list<'a>(open: char, close: char, input: &'a str) -> Res<'a, Vec<OptionPair<'a>>> {
+    let pair = |i: &'a str| -> Res<'a, OptionPair<'a>> {
+        let (i, key) = context("Expected an option name", alpha1)(i)?;
+        let (value_at, _) = cut(context(
+            "Expected '=' after the option name",
+            tuple((multispace0, char('='), multispace0)),
+        ))(i)?;
+        let (i, value) = cut(context(
+            "Expected a quoted value or a number",
+            alt((quoted_value, map(digit1, String::from))),
+        ))(value_at)?;
+        Ok((i, OptionPair { key, value, value_at }))
+    };
+
+    preceded(
+        char(open),
+        cut(terminated(
+            delimited(
+                multispace0,
+                separated_list0(tuple((multispace0, char(','), multispace0)), pair),
+                tuple((multispace0, opt(char(',')), multispace0)),
+            ),
+            context(if close == ')' { "Expected ')' to close the options" } else { "Expected ']' to close the details" }, char(close)),
+        )),
+    )(input)
+}
+
+
+/// Turns a parsed type and its arguments into an [`InputField`]
+fn build_field<'a>(kind: &'a str, req: bool, arguments: Option<Arguments<'a>>) -> Result<InputField, (&'a str, String)> {
+    let raw = |name: &str| -> Result<&'a str, (&'a str, String)> {
+        match &arguments {
+            Some(Arguments::Raw(v)) => Ok(v),
+            _ => Err((kind, format!("{name} needs an argument in parentheses, e.g. {name}(...)"))),
+        }
+    };
+    let no_arguments = |field: InputField| -> Result<InputField, (&'a str, String)> {
+        match arguments {
+            None => Ok(field),
+            Some(_) => Err((kind, format!("{kind} does not take arguments"))),
+        }
+    };
+
+    match kind {
+        "String" => {
+            let options = match &arguments {
+                Some(Arguments::Options(v)) => v.clone(),
+                _ => Vec::new(),
+            };
+            Ok(InputField::String { req, rules: build_rules(options)? })
+        },
+        "Number" => no_arguments(InputField::Number { req }),
+        "Bool" => no_arguments(InputField::Bool { req }),
+        "Date" => no_arguments(InputField::Date { req }),
+        "DateTime" => no_arguments(InputField::DateTime { req }),
+        "Duration" => no_arguments(InputField::Duration { req }),
+        "Url" => no_arguments(InputField::Url { req }),
+        "Doi" => no_arguments(InputField::Doi { req }),
+        "Created" => no_arguments(InputField::Created),
+        "Modified" => no_arguments(InputField::Modified),
+        "Author" => no_arguments(InputField::Author),
+        "One" => Ok(InputField::One { req, id: raw(kind)?.trim().to_string() }),
+        "Multi" => Ok(InputField::Multi { req, id: raw(kind)?.trim().to_string() }),
+        "Ref" | "Refs" => {
+            let capture = raw(kind)?;
+            let dir = capture.trim().trim_end_matches('/');
+            if dir.is_empty() || !workspace::stays_inside(dir) {
+                return Err((capture, "References must point to a directory inside the workspace, without '..'".to_string()));
+            }
+            match kind {
+                "Ref" => Ok(InputField::Ref { req, dir: dir.to_string() }),
+                _ => Ok(InputField::Refs { req, dir: dir.to_string() }),
+            }
+        },
+        "Choice" | "Choices" => {
+            let capture = raw(kind)?;
+            let options: Vec<String> = capture.split('|')
+                .map(|option| option.trim().to_string())
+                .collect();
+            if options.iter().any(|option| option.is_empty()) {
+                return Err((capture, "Choice lists cannot contain empty options".to_string()));
+            }
+            match kind {
+                "Choice" => Ok(InputField::Choice { req, options }),
+                _ => Ok(InputField::Choices { req, options }),
+            }
+        },
+        "Seq" => {
+            let capture = raw(kind)?;
+            let pattern = capture.trim().to_string();
+            if auto_fields::split_pattern(&pattern).is_none() {
+                return Err((capture, "Sequence patterns need a single run of '#', e.g. EXP-####".to_string()));
+            }
+            Ok(InputField::Seq { pattern })
+        },
+        _ => Err((kind, format!("Unknown attribute type \"{kind}\""))),
+    }
+}
+
+
+/// Turns the options of a `String` type into [`StringRules`]
+fn build_rules(options: Vec<OptionPair<'_>>) -> Result<StringRules, (&str, String)> {
+    let mut rules = StringRules::default();
+    for OptionPair { key, value, value_at } in options {
+        let length = || value.parse::<usize>()
+            .map_err(|_| (value_at, format!("String rule \"{key}\" must be a number")));
+        match key {
+            "pattern" => {
+                let regex = Regex::new(&value).map_err(|e| (value_at, format!("Invalid pattern: {e}")))?;
+                rules.pattern = Some(regex);
+            },
+            "min" => rules.min = Some(length()?),
+            "max" => rules.max = Some(length()?),
+            "message" => rules.message = Some(value),
+            _ => return Err((key, format!("Unknown String rule \"{key}\""))),
+        }
+    }
+    Ok(rules)
+}
+
+
+/// Collects the default value and bracketed details of a declaration
+fn build_info<'a>(field: &InputField, default: Option<String>, details: Vec<OptionPair<'a>>) -> Result<AttributeInfo, (Option<&'a str>, String)> {
+    let mut info = AttributeInfo { default, ..AttributeInfo::default() };
+    for OptionPair { key, value, .. } in details {
+        match key {
+            "placeholder" => info.placeholder = Some(value),
+            "help" => info.help = Some(value),
+            _ => return Err((Some(key), format!("Unknown attribute detail \"{key}\""))),
+        }
+    }
+    if info.default.is_some() && field.is_auto() {
+        return Err((None, "Auto-populated attributes cannot have a default value".to_string()));
+    }
+    if let (Some(default), InputField::Number { .. } | InputField::Bool { .. }) = (&info.default, field) {
+        field.validate(&FieldValue::from_input(field, default))
+            .map_err(|e| (None, format!("Invalid default value. {e}")))?;
+    }
+    Ok(info)
+}
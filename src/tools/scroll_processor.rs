@@ -1,67 +1,29 @@
-use crate::prelude::*;
+use crate::{
+    prelude::*,
+    tools::{csv_parser, workspace},
+};
 use std::fs::{read_to_string, read_dir};
-use nom::{
-    bytes::complete::{tag, take_until},
-    error::ErrorKind,
-    sequence::delimited};
-
-
-
-pub fn parse_pairs(pairs: &str) -> Result<Vec<(&str, &str)>, usize> {
-    pairs.lines()
-        .enumerate()
-        .filter(|(_, line)| !line.trim().is_empty())
-        .map(|(i, line)| {
-            let line_num = i + 1;
-            let parts: Vec<&str> = line.split(":").collect();
-            if parts.len() == 2 {
-                assert!(parts.get(0).is_some() && parts.get(1).is_some(),
-                    "ERR[0|0]: Attribute key-value pair improperly validated.");
-                Ok((parts.get(0).unwrap().trim(), parts.get(1).unwrap().trim()))
-            } else {
-                Err(line_num)
-            }
-        })
-        .collect()
-}
 
 
-pub fn parse_attribute(mut s: &str) -> Result<InputField, String> {
-    let asterisk = s.starts_with("*");
-    if asterisk {
-        s = &s[1..s.len()];    
-    }
-    let basic_parse = |keyword: &str| -> bool {
-        tag::<_, _, (_, ErrorKind)>(keyword)(s).is_ok()
-    };
-    let advanced_parse = |stub: &str| -> Option<String> {
-        let search_string = format!("{stub}("); 
-        let result = delimited(
-            tag::<_,_,(_, ErrorKind)>(search_string.as_str()),
-            take_until(")"),
-            tag(")")
-        )(s);
-        match result {
-            Ok((_, parsed_content)) => {
-                Some(parsed_content.to_string())
-            }
-            Err(_) => None,
-        }
-    };
-        
-    if basic_parse("String") {
-        return Ok(InputField::String { req: asterisk });
-    }
-    if basic_parse("Date") {
-        return Ok(InputField::Date { req: asterisk });
-    }
-    if let Some(capture) = advanced_parse("One") {
-        return Ok(InputField::One { req: asterisk, id: capture });
-    }
-    if let Some(capture) = advanced_parse("Multi") {
-        return Ok(InputField::Multi { req: asterisk, id: capture });
+
+/// Lists the entries of another directory that a `Ref` or `Refs` attribute can point to
+///
+/// # Props
+/// - `dir`: The referenced directory, relative to `DOC_DIR`
+///
+/// # Returns
+/// - `Ok` with the sorted `__ID`s found in the directory's `.database.json`
+/// - `Err(e)` if the database cannot be read
+pub fn ref_query(dir: &str) -> Result<Vec<String>, String> {
+    if !workspace::stays_inside(dir) {
+        return Err(format!("ERR(0|1): Referenced directory {dir} is outside the workspace."));
     }
-    return Err("Malformed attribute syntax.".to_string());
+    let db_path = DOC_DIR.read().unwrap().join(dir).join(".database.json");
+    let objects = json_processor::get_json_hashmap(&db_path)
+        .map_err(|e| format!("ERR(0|1): Referenced directory {dir} could not be read. {e}"))?;
+    let mut ids: Vec<String> = objects.into_keys().collect();
+    ids.sort();
+    Ok(ids)
 }
 
 
@@ -79,18 +41,124 @@ pub fn db_query(list_id: &str) -> Result<(Vec<Vec<String>>, Vec<String>), String
     let search_result = guard
         .get(list_id)
         .ok_or("ERR(0|1): Key not found in the database".to_string())?;
-    if search_result.0.is_empty() || search_result.1.is_empty() {
+    if search_result.0.is_empty() {
         return Err(format!("ERR(0|1): Database {list_id} is empty!"));
     }
     Ok(search_result.clone())
 }
 
 
+/// Groups the rows of a table by its `Category` column, if it has one
+///
+/// Categories are paths such as `Enzymes > Restriction`. Groups are sorted by path, so a category comes right
+/// before its subcategories, and uncategorised rows come first under an empty path.
+///
+/// # Props
+/// - `content`: The table, header first
+///
+/// # Returns
+/// The path of each group with the indices of its rows in `content`
+pub fn group_rows(content: &[Vec<String>]) -> Vec<(String, Vec<usize>)> {
+    let column = content.first()
+        .and_then(|header| header.iter().position(|title| title.eq_ignore_ascii_case("category")));
+    let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+
+    for (i, row) in content.iter().enumerate().skip(1) {
+        let path = column
+            .and_then(|column| row.get(column))
+            .map(|category| category.split('>')
+                .map(|part| part.trim())
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<&str>>()
+                .join(" > "))
+            .unwrap_or_default();
+        match groups.iter_mut().find(|(existing, _)| *existing == path) {
+            Some((_, rows)) => rows.push(i),
+            None => groups.push((path, vec![i])),
+        }
+    }
+    groups.sort_by(|a, b| a.0.cmp(&b.0));
+    groups
+}
+
+
+/// Finds the file behind a table of `DOC_DIR/sys`
+fn db_path(list_id: &str) -> Result<PathBuf> {
+    let base_path = DOC_DIR.read().unwrap().join("sys");
+    ["scroll", "csv", "tsv"].iter()
+        .map(|extension| base_path.join(list_id).with_extension(extension))
+        .find(|path| path.exists())
+        .ok_or(Report::msg(format!("Database {list_id} has no file in {}", base_path.display())))
+}
+
+
+/// Writes a table back to its file in `DOC_DIR/sys` and refreshes its entry in `DATABASE_HOLD`
+///
+/// # Props
+/// - `list_id`: The name of the table
+/// - `rows`: Every row of the table, header first
+///
+/// # Returns
+/// - `Ok` if the file was written and read back
+/// - `Err(e)` if the file cannot be found, written or parsed again
+pub fn write_db(list_id: &str, rows: &[Vec<String>]) -> Result<()> {
+    let path = db_path(list_id)?;
+    let extension = path.extension().unwrap_or_default().to_string_lossy().into_owned();
+    // Keep the layout of the existing file, e.g. `a, b` in hand-written `.scroll` tables
+    let separator = match (csv_parser::delimiter_for(&extension, &read_to_string(&path)?), extension.as_str()) {
+        ('\t', _) => "\t",
+        (_, "csv") => ",",
+        _ => ", ",
+    };
+    std::fs::write(&path, csv_parser::write(rows, separator))?;
+    reload_db(list_id)
+}
+
+
+/// Creates a new table in `DOC_DIR/sys` as a `.scroll` file and loads it into `DATABASE_HOLD`
+///
+/// # Returns
+/// - `Ok` if the file was written and read back
+/// - `Err(e)` if a table of that name already exists or the file cannot be written
+pub fn create_db(list_id: &str, rows: &[Vec<String>]) -> Result<()> {
+    if db_path(list_id).is_ok() {
+        return Err(Report::msg(format!("Database {list_id} already exists")));
+    }
+    let path = DOC_DIR.read().unwrap().join("sys").join(list_id).with_extension("scroll");
+    std::fs::write(&path, csv_parser::write(rows, ", "))?;
+    reload_db(list_id)
+}
+
+
+/// Parses a table of `DOC_DIR/sys` again after its file changed, dropping it if the file is gone
+///
+/// # Returns
+/// - `Ok` if `DATABASE_HOLD` is up to date
+/// - `Err(e)` if the file cannot be parsed, in which case the previous contents are kept
+pub fn reload_db(list_id: &str) -> Result<()> {
+    match db_path(list_id) {
+        Ok(path) => {
+            let (rows, ids, warnings) = parse_db(&path)?;
+            DATABASE_HOLD.write().unwrap().insert(list_id.to_string(), (rows, ids));
+            set_warnings(list_id, warnings);
+        },
+        Err(_) => {
+            DATABASE_HOLD.write().unwrap().remove(list_id);
+            set_warnings(list_id, Vec::new());
+        },
+    }
+    Ok(())
+}
+
+
+/// Parses every table in `DOC_DIR/sys`, keyed by file name without extension
+///
+/// `.scroll` and `.csv` files are read as comma-separated, `.tsv` files as tab-separated.
 pub fn parse_all_databases() -> Result<HashMap<String, (Vec<Vec<String>>, Vec<String>)>> {
     let base_path = DOC_DIR.read().unwrap().join("sys");
     let databases: Vec<PathBuf> = read_dir(base_path)?
         .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "scroll"))
+        .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "scroll" || ext == "csv" || ext == "tsv"))
         .map(|entry| entry.path())
         .collect();
 
@@ -100,8 +168,9 @@ pub fn parse_all_databases() -> Result<HashMap<String, (Vec<Vec<String>>, Vec<St
                 .unwrap_or_default()
                 .to_string_lossy()
                 .into_owned();
-            let data_result = parse_db(path)?;
-            Ok((name, data_result))
+            let (rows, ids, warnings) = parse_db(path)?;
+            set_warnings(&name, warnings);
+            Ok((name, (rows, ids)))
         })
         .collect();
 
@@ -109,31 +178,62 @@ pub fn parse_all_databases() -> Result<HashMap<String, (Vec<Vec<String>>, Vec<St
 }
 
 
-fn parse_db(path: &PathBuf) -> Result<(Vec<Vec<String>>, Vec<String>)> {
-    let content = read_to_string(path)?;
-    let first_line: Vec<&str> = content.lines()
-        .next().ok_or(Report::msg(format!("Database at {} is empty", path.display())))?
-        .split(", ").collect();
-    let width = first_line.len();
-
-    let result = content.lines()
-        .map(|line| {
-            let split = line.split(", ");
-            let first = split.clone().next().unwrap_or_default().to_string();
-            let all = split.map(String::from).collect::<Vec<String>>();
-            if all.len() == width {
-                Ok((all, first))
-            } else {
-                Err(Report::msg(format!("Database at {} has uneven widths", path.display())))
-            }
-        })
-        .collect::<Result<(Vec<Vec<String>>, Vec<String>)>>();
+/// Problems of a table that did not stop it from loading, e.g. keys used twice
+pub fn warnings(list_id: &str) -> Vec<String> {
+    DATABASE_WARNINGS.read().unwrap().get(list_id).cloned().unwrap_or_default()
+}
+
+
+fn set_warnings(list_id: &str, warnings: Vec<String>) {
+    let mut guard = DATABASE_WARNINGS.write().unwrap();
+    match warnings.is_empty() {
+        true => guard.remove(list_id),
+        false => guard.insert(list_id.to_string(), warnings),
+    };
+}
+
+
+/// Every row of a table, the keys below its header, and the problems that did not stop it from loading
+type ParsedTable = (Vec<Vec<String>>, Vec<String>, Vec<String>);
 
-    let mut data_tuple: (Vec<Vec<String>>, Vec<String>) = result?;
 
-    if !data_tuple.1.is_empty() {
-        data_tuple.1.remove(0);
+/// Reads one table as RFC 4180 CSV, with the header as its first row
+///
+/// Entries store only the key, so it has to identify a row even across categories. A key used again
+/// further down is reported and that row left out, so one bad line does not keep the app from starting.
+///
+/// # Returns
+/// - `Ok` with every row, separately the first field of each row below the header, and the problems found
+/// - `Err(e)` naming the file, line and column of the first problem that makes the table unreadable
+fn parse_db(path: &PathBuf) -> Result<ParsedTable> {
+    let content = read_to_string(path)?;
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    let delimiter = csv_parser::delimiter_for(&extension, &content);
+
+    let lines = csv_parser::parse_lines(&content, delimiter)
+        .map_err(|e| Report::msg(format!("Database at {}, {e}", path.display())))?;
+    if lines.is_empty() {
+        return Err(Report::msg(format!("Database at {} is empty", path.display())));
     }
 
-    Ok(data_tuple)
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut ids: Vec<String> = Vec::new();
+    let mut warnings: Vec<String> = Vec::new();
+    let mut first_lines: HashMap<String, usize> = HashMap::new();
+    for (i, (line, row)) in lines.into_iter().enumerate() {
+        if i > 0 {
+            let id = row.first().cloned().unwrap_or_default();
+            if let Some(first) = first_lines.get(&id) {
+                warnings.push(format!(
+                    "{}, line {line}: the key \"{id}\" is already used on line {first}; this row is ignored",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                ));
+                continue;
+            }
+            first_lines.insert(id.clone(), line);
+            ids.push(id);
+        }
+        rows.push(row);
+    }
+    Ok((rows, ids, warnings))
 }
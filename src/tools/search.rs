@@ -0,0 +1,288 @@
+use crate::{
+    prelude::*,
+    tools::workspace,
+};
+use homedir::my_home;
+use serde_json::{Map, Value};
+use std::{
+    fs::{create_dir_all, read_dir, read_to_string, write},
+    path::Path,
+    sync::Mutex,
+    time::UNIX_EPOCH,
+};
+use tantivy::{
+    Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term,
+    collector::TopDocs,
+    directory::MmapDirectory,
+    doc,
+    query::QueryParser,
+    schema::{Field, Schema, Value as _, STORED, STRING, TEXT},
+    snippet::{Snippet, SnippetGenerator},
+};
+
+
+
+/// Memory given to the index writer; entries are small, so the minimum is plenty
+const WRITER_MEMORY: usize = 15_000_000;
+/// Longest snippet shown under a result, in characters
+const SNIPPET_CHARS: usize = 160;
+
+
+static INDEX: LazyLock<Result<SearchIndex, String>> = LazyLock::new(|| SearchIndex::open().map_err(|e| e.to_string()));
+
+
+
+/// A search result
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hit {
+    /// The directory holding the entry
+    pub dir: PathBuf,
+    pub entry: String,
+    /// Pieces of the best matching passage, with `true` for the highlighted ones
+    pub snippet: Vec<(String, bool)>,
+}
+
+
+struct SearchIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    /// Stamp of every indexed directory, the newest modification time of it and its records and entries
+    stamps: Mutex<Map<String, Value>>,
+    stamps_path: PathBuf,
+    dir: Field,
+    entry: Field,
+    title: Field,
+    body: Field,
+    metadata: Field,
+}
+
+
+impl SearchIndex {
+    /// Opens the index of the open workspace, kept in `~/.cache/scroll/search`, creating it on first use
+    ///
+    /// Entries are stored relative to `DOC_DIR`, so each workspace gets its own index, named after a
+    /// hash of its canonical path.
+    fn open() -> Result<Self> {
+        let home = my_home()?.ok_or(Report::msg("No home directory"))?;
+        let path = home.join(".cache/scroll/search").join(format!("{:016x}", fnv1a(&workspace::identity()?)));
+        create_dir_all(&path)?;
+
+        let mut builder = Schema::builder();
+        let dir = builder.add_text_field("dir", STRING | STORED);
+        let entry = builder.add_text_field("entry", STRING | STORED);
+        let title = builder.add_text_field("title", TEXT);
+        let body = builder.add_text_field("body", TEXT | STORED);
+        let metadata = builder.add_text_field("metadata", TEXT | STORED);
+        let index = Index::open_or_create(MmapDirectory::open(&path)?, builder.build())?;
+
+        let stamps_path = path.join("stamps.json");
+        let stamps = match read_to_string(&stamps_path).ok().and_then(|text| serde_json::from_str(&text).ok()) {
+            Some(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+        Ok(SearchIndex {
+            reader: index.reader_builder().reload_policy(ReloadPolicy::OnCommitWithDelay).try_into()?,
+            writer: Mutex::new(index.writer(WRITER_MEMORY)?),
+            index,
+            stamps: Mutex::new(stamps),
+            stamps_path,
+            dir,
+            entry,
+            title,
+            body,
+            metadata,
+        })
+    }
+
+
+    /// Replaces the documents of a directory with its current entries
+    fn index_dir(&self, writer: &IndexWriter, dir: &Path, key: &str) -> Result<()> {
+        writer.delete_term(Term::from_field_text(self.dir, key));
+        let db_path = dir.join(".database.json");
+        if !db_path.is_file() {
+            return Ok(());
+        }
+        for (id, record) in json_processor::get_json_hashmap(&db_path)? {
+            let body = read_to_string(dir.join(format!("{id}.md"))).unwrap_or_default();
+            let mut fields: Vec<(&String, &Value)> = record.iter().filter(|(k, _)| *k != "__ID").collect();
+            fields.sort_by(|a, b| a.0.cmp(b.0));
+            let metadata = fields.into_iter()
+                .map(|(k, v)| format!("{k}: {}", FieldValue::from_json(v, None)))
+                .collect::<Vec<String>>()
+                .join("\n");
+            writer.add_document(doc!(
+                self.dir => key,
+                self.entry => id.as_str(),
+                self.title => deserialize(&id),
+                self.body => body,
+                self.metadata => metadata,
+            ))?;
+        }
+        Ok(())
+    }
+
+
+    fn save_stamps(&self, stamps: &Map<String, Value>) -> Result<()> {
+        write(&self.stamps_path, serde_json::to_string_pretty(&Value::Object(stamps.clone()))?)?;
+        Ok(())
+    }
+}
+
+
+
+/// 64-bit FNV-1a hash, which unlike the standard hasher stays the same across Rust versions
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+
+fn index() -> Result<&'static SearchIndex> {
+    INDEX.as_ref().map_err(|e| Report::msg(format!("Search index unavailable. {e}")))
+}
+
+
+/// Newest modification time of a directory and its records and entries, in nanoseconds
+///
+/// The directory itself is included so a deleted entry counts as a change, and sub-second precision keeps
+/// an edit made right after indexing from being missed.
+fn stamp(dir: &PathBuf) -> u64 {
+    let entries = match read_dir(dir) {
+        Ok(v) => v,
+        Err(_) => return 0,
+    };
+    entries.filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "md") || path.ends_with(".database.json"))
+        .chain(std::iter::once(dir.clone()))
+        .filter_map(|path| path.metadata().ok()?.modified().ok()?.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX))
+        .max()
+        .unwrap_or(0)
+}
+
+
+/// Brings the index up to date with the whole workspace
+///
+/// Only directories changed since they were last indexed are read again, and directories that no
+/// longer hold records are dropped.
+///
+/// # Returns
+/// The number of directories indexed again
+pub fn sync() -> Result<usize> {
+    let index = index()?;
+    let mut writer = index.writer.lock().map_err(|e| Report::msg(e.to_string()))?;
+    let mut stamps = index.stamps.lock().map_err(|e| Report::msg(e.to_string()))?;
+
+    let dirs: Vec<(String, PathBuf)> = workspace::database_dirs().into_iter()
+        .filter_map(|dir| workspace::relative_to_docs(&dir).map(|key| (key, dir)))
+        .collect();
+    let vanished: Vec<String> = stamps.keys()
+        .filter(|key| !dirs.iter().any(|(other, _)| other == *key))
+        .cloned()
+        .collect();
+    for key in vanished {
+        writer.delete_term(Term::from_field_text(index.dir, &key));
+        stamps.remove(&key);
+    }
+
+    let mut updated = 0;
+    for (key, dir) in dirs.iter() {
+        let current = stamp(dir);
+        if stamps.get(key).and_then(|v| v.as_u64()) == Some(current) {
+            continue;
+        }
+        index.index_dir(&writer, dir, key)?;
+        stamps.insert(key.clone(), Value::from(current));
+        updated += 1;
+    }
+    writer.commit()?;
+    index.save_stamps(&stamps)?;
+    Ok(updated)
+}
+
+
+/// Indexes a single directory again, e.g. after the workspace watcher saw it change
+pub fn update_dir(dir: &PathBuf) -> Result<()> {
+    let index = index()?;
+    let key = workspace::relative_to_docs(dir)
+        .ok_or(Report::msg(format!("{} is outside the workspace", dir.display())))?;
+    let mut writer = index.writer.lock().map_err(|e| Report::msg(e.to_string()))?;
+    let mut stamps = index.stamps.lock().map_err(|e| Report::msg(e.to_string()))?;
+
+    index.index_dir(&writer, dir, &key)?;
+    match dir.join(".database.json").is_file() {
+        true => stamps.insert(key, Value::from(stamp(dir))),
+        false => stamps.remove(&key),
+    };
+    writer.commit()?;
+    index.save_stamps(&stamps)?;
+    Ok(())
+}
+
+
+/// Searches the titles, bodies and metadata of every indexed entry
+///
+/// # Props
+/// - `text`: What the user typed; every word has to match, and `"quoted phrases"` are kept together
+/// - `limit`: The most results returned
+///
+/// # Returns
+/// The best matches first, each with a highlighted passage of its body or metadata
+pub fn search(text: &str, limit: usize) -> Result<Vec<Hit>> {
+    let index = index()?;
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let searcher = index.reader.searcher();
+    let mut parser = QueryParser::for_index(&index.index, vec![index.title, index.body, index.metadata]);
+    parser.set_conjunction_by_default();
+    parser.set_field_boost(index.title, 2.0);
+    // Stray operators are ignored rather than reported while the user is typing
+    let (query, _) = parser.parse_query_lenient(text);
+
+    let mut body_snippets = SnippetGenerator::create(&searcher, &*query, index.body)?;
+    body_snippets.set_max_num_chars(SNIPPET_CHARS);
+    let mut metadata_snippets = SnippetGenerator::create(&searcher, &*query, index.metadata)?;
+    metadata_snippets.set_max_num_chars(SNIPPET_CHARS);
+
+    let root = DOC_DIR.read().map_err(|e| Report::msg(e.to_string()))?.clone();
+    let mut hits = Vec::new();
+    for (_, address) in searcher.search(&query, &TopDocs::with_limit(limit))? {
+        let document: TantivyDocument = searcher.doc(address)?;
+        let text_of = |field: Field| document.get_first(field).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        let key = text_of(index.dir);
+        let mut snippet = body_snippets.snippet(&text_of(index.body));
+        if snippet.is_empty() {
+            snippet = metadata_snippets.snippet(&text_of(index.metadata));
+        }
+        hits.push(Hit {
+            dir: match key.as_str() {
+                "/" => root.clone(),
+                _ => root.join(&key),
+            },
+            entry: text_of(index.entry),
+            snippet: pieces(&snippet),
+        });
+    }
+    Ok(hits)
+}
+
+
+/// Splits a snippet into plain and highlighted pieces
+fn pieces(snippet: &Snippet) -> Vec<(String, bool)> {
+    let fragment = snippet.fragment();
+    let mut result = Vec::new();
+    let mut last = 0;
+    for range in snippet.highlighted() {
+        if range.start > last {
+            result.push((fragment[last..range.start].to_string(), false));
+        }
+        result.push((fragment[range.clone()].to_string(), true));
+        last = range.end;
+    }
+    if last < fragment.len() {
+        result.push((fragment[last..].to_string(), false));
+    }
+    result
+}
@@ -0,0 +1,150 @@
+use crate::{
+    prelude::*,
+    tools::csv_parser,
+};
+use calamine::{open_workbook_auto, Data, Reader};
+use chrono::Timelike;
+use std::fs::read_to_string;
+
+
+
+/// A sheet of a workbook, or the only sheet of a CSV file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sheet {
+    pub name: String,
+    pub rows: Vec<Vec<String>>,
+}
+
+
+/// The outcome of importing rows into a sys database, computed before anything is written
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportPlan {
+    /// The whole table after the import, header first
+    pub rows: Vec<Vec<String>>,
+    /// Keys of rows that are new to the database
+    pub added: Vec<String>,
+    /// Keys of existing rows replaced by imported values
+    pub updated: Vec<String>,
+    /// Keys of existing rows kept as they are
+    pub skipped: Vec<String>,
+    /// Keys that appear more than once in the imported file; only the first row is used
+    pub repeated: Vec<String>,
+    /// Number of imported rows left out because their key column is empty
+    pub keyless: usize,
+}
+
+
+
+/// Reads the sheets of an XLSX, XLS, ODS, CSV or TSV file
+///
+/// # Returns
+/// - `Ok` with every sheet, its rows padded to a common width
+/// - `Err(e)` if the file cannot be opened or parsed
+pub fn read_sheets(path: &PathBuf) -> Result<Vec<Sheet>> {
+    let extension = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+    let mut sheets = match extension.as_str() {
+        "csv" | "tsv" | "txt" => {
+            let content = read_to_string(path)?;
+            let delimiter = csv_parser::delimiter_for(&extension, &content);
+            let rows = csv_parser::parse(&content, delimiter)
+                .map_err(|e| Report::msg(format!("{}, {e}", path.display())))?;
+            let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            vec![Sheet { name, rows }]
+        },
+        _ => {
+            let mut workbook = open_workbook_auto(path)?;
+            let mut sheets = Vec::new();
+            for name in workbook.sheet_names() {
+                let range = workbook.worksheet_range(&name)?;
+                let rows = range.rows()
+                    .map(|row| row.iter().map(cell_to_string).collect())
+                    .collect();
+                sheets.push(Sheet { name, rows });
+            }
+            sheets
+        },
+    };
+
+    for sheet in sheets.iter_mut() {
+        sheet.rows.retain(|row| row.iter().any(|cell| !cell.trim().is_empty()));
+        let width = sheet.rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        for row in sheet.rows.iter_mut() {
+            row.resize(width, String::new());
+        }
+    }
+    Ok(sheets)
+}
+
+
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::DateTime(value) => match value.as_datetime() {
+            Some(datetime) if datetime.num_seconds_from_midnight() == 0 => datetime.format("%Y-%m-%d").to_string(),
+            Some(datetime) => datetime.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            None => value.to_string(),
+        },
+        other => other.to_string().trim().to_string(),
+    }
+}
+
+
+/// Guesses which source column feeds each column of a database by comparing titles
+///
+/// # Returns
+/// For every column of `header`, the index of the source column with the same title ignoring case and spaces
+pub fn match_columns(header: &[String], source: &[String]) -> Vec<Option<usize>> {
+    let simplify = |title: &str| title.to_lowercase().replace([' ', '_', '-'], "");
+    header.iter()
+        .map(|title| source.iter().position(|candidate| simplify(candidate) == simplify(title)))
+        .collect()
+}
+
+
+/// Merges imported rows into a table, keyed by the first column
+///
+/// # Props
+/// - `existing`: The current table, header first, or just the header for a new database
+/// - `imported`: The rows of the sheet without its header
+/// - `mapping`: For every column of the table, the sheet column it is filled from
+/// - `overwrite`: Whether imported rows update existing rows with the same key
+pub fn plan(existing: &[Vec<String>], imported: &[Vec<String>], mapping: &[Option<usize>], overwrite: bool) -> ImportPlan {
+    let mut result = ImportPlan { rows: existing.to_vec(), ..ImportPlan::default() };
+    let mut seen: Vec<String> = Vec::new();
+
+    for source in imported {
+        let row: Vec<String> = mapping.iter()
+            .map(|column| column.and_then(|c| source.get(c)).map(|cell| cell.trim().to_string()).unwrap_or_default())
+            .collect();
+        let key = row.first().cloned().unwrap_or_default();
+        if key.is_empty() {
+            result.keyless += 1;
+            continue;
+        }
+        if seen.contains(&key) {
+            if !result.repeated.contains(&key) {
+                result.repeated.push(key);
+            }
+            continue;
+        }
+        seen.push(key.clone());
+
+        match result.rows.iter().skip(1).position(|other| other.first() == Some(&key)) {
+            Some(i) if overwrite => {
+                // Columns without a source keep their current values
+                let current = &mut result.rows[i + 1];
+                for (c, value) in row.into_iter().enumerate() {
+                    if mapping[c].is_some() {
+                        current[c] = value;
+                    }
+                }
+                result.updated.push(key);
+            },
+            Some(_) => result.skipped.push(key),
+            None => {
+                result.rows.push(row);
+                result.added.push(key);
+            },
+        }
+    }
+    result
+}
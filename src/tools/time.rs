@@ -0,0 +1,142 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone};
+
+
+
+/// Parses a stored or user-entered date-time
+///
+/// # Props
+/// - `value`: An ISO-8601 date-time with offset, or a local `datetime-local` value
+///
+/// # Returns
+/// - `Some` with the parsed time, keeping its original offset
+/// - `None` if `value` is not a valid date-time
+pub fn parse_datetime(value: &str) -> Option<DateTime<FixedOffset>> {
+    let trimmed = value.trim();
+    if let Ok(v) = DateTime::parse_from_rfc3339(trimmed) {
+        return Some(v);
+    }
+    let naive = NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%dT%H:%M")
+        .or(NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%dT%H:%M:%S"))
+        .ok()?;
+    Local.from_local_datetime(&naive)
+        .earliest()
+        .map(|v| v.fixed_offset())
+}
+
+
+/// Converts a date-time to its ISO-8601 storage form, e.g. `2025-06-01T14:30:00+02:00`
+pub fn datetime_to_iso(value: &str) -> Option<String> {
+    parse_datetime(value).map(|v| v.to_rfc3339())
+}
+
+
+/// Converts a stored date-time to the local value expected by a `datetime-local` input
+pub fn datetime_to_input(value: &str) -> String {
+    parse_datetime(value)
+        .map(|v| v.with_timezone(&Local).format("%Y-%m-%dT%H:%M").to_string())
+        .unwrap_or_default()
+}
+
+
+/// Formats a stored date-time for display in the local timezone
+pub fn datetime_to_display(value: &str) -> String {
+    parse_datetime(value)
+        .map(|v| v.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or(value.to_string())
+}
+
+
+/// Parses a duration into seconds
+///
+/// # Props
+/// - `value`: A shorthand duration (`2h30m`, `1d 4h`, `45s`) or an ISO-8601 duration (`PT2H30M`)
+///
+/// # Returns
+/// - `Some` with the total number of seconds
+/// - `None` if `value` is empty, malformed or too large
+pub fn parse_duration(value: &str) -> Option<i64> {
+    let upper = value.trim().to_uppercase();
+    let (body, iso) = match upper.strip_prefix('P') {
+        Some(v) => (v.to_string(), true),
+        None => (upper.replace(' ', ""), false),
+    };
+
+    // Shorthand has no date/time separator, so `M` always means minutes
+    let mut in_time = !iso;
+    let mut total: Option<i64> = None;
+    let mut digits = String::new();
+    for c in body.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        if iso && c == 'T' && digits.is_empty() && !in_time {
+            in_time = true;
+            continue;
+        }
+        let amount: i64 = digits.parse().ok()?;
+        digits.clear();
+        let unit = match c {
+            'D' => 86_400,
+            'H' if in_time => 3_600,
+            'M' if in_time => 60,
+            'S' if in_time => 1,
+            _ => return None,
+        };
+        // Long digit runs typed into the field must not overflow
+        total = Some(total.unwrap_or(0).checked_add(amount.checked_mul(unit)?)?);
+    }
+    if !digits.is_empty() {
+        return None;
+    }
+    total
+}
+
+
+/// Converts a duration to its ISO-8601 storage form, e.g. `PT2H30M`
+pub fn duration_to_iso(value: &str) -> Option<String> {
+    let seconds = parse_duration(value)?;
+    let (days, hours, minutes, secs) = split_seconds(seconds);
+    let mut iso = "P".to_string();
+    if days > 0 {
+        iso.push_str(&format!("{days}D"));
+    }
+    if hours > 0 || minutes > 0 || secs > 0 || days == 0 {
+        iso.push('T');
+        if hours > 0 {
+            iso.push_str(&format!("{hours}H"));
+        }
+        if minutes > 0 {
+            iso.push_str(&format!("{minutes}M"));
+        }
+        if secs > 0 || (hours == 0 && minutes == 0) {
+            iso.push_str(&format!("{secs}S"));
+        }
+    }
+    Some(iso)
+}
+
+
+/// Formats a stored duration in shorthand, e.g. `2h 30m`
+pub fn duration_to_display(value: &str) -> String {
+    let seconds = match parse_duration(value) {
+        Some(v) => v,
+        None => return value.to_string(),
+    };
+    let (days, hours, minutes, secs) = split_seconds(seconds);
+    let parts: Vec<String> = [(days, "d"), (hours, "h"), (minutes, "m"), (secs, "s")]
+        .iter()
+        .filter(|(amount, _)| *amount > 0)
+        .map(|(amount, unit)| format!("{amount}{unit}"))
+        .collect();
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+
+fn split_seconds(seconds: i64) -> (i64, i64, i64, i64) {
+    (seconds / 86_400, seconds % 86_400 / 3_600, seconds % 3_600 / 60, seconds % 60)
+}
@@ -0,0 +1,154 @@
+use crate::{
+    prelude::*,
+    tools::{compare::EmptyPlacement, query::ColumnFilter},
+    types::files::{Order, SortDirection},
+};
+use serde_json::{json, Map, Value};
+use std::{
+    fs::{read_to_string, remove_file, write},
+    path::Path,
+};
+
+
+
+/// A named way of looking at a directory, shared with everyone through `.views.json`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct View {
+    pub name: String,
+    /// Text of the query bar
+    pub query: String,
+    /// Filters of the row under the headers, by attribute title
+    pub column_filters: HashMap<String, ColumnFilter>,
+    pub ordering: Order,
+    /// Titles of the attributes left out of the table
+    pub hidden_columns: Vec<String>,
+    /// Title of the attribute rows are grouped by
+    pub group_by: Option<String>,
+}
+
+
+
+/// Reads the saved views of a directory
+///
+/// # Returns
+/// - `Ok` with the views in file order, or none if the directory has no `.views.json`
+/// - `Err(e)` if the file exists but cannot be read
+pub fn load(dir: &Path) -> Result<Vec<View>> {
+    let path = dir.join(".views.json");
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    match serde_json::from_str(&read_to_string(&path)?)? {
+        Value::Array(views) => Ok(views.iter().filter_map(view_from_json).collect()),
+        _ => Err(Report::msg(format!("{} is not a list of views", path.display()))),
+    }
+}
+
+
+/// Writes the views of a directory, removing `.views.json` once the last view is deleted
+pub fn save(dir: &Path, views: &[View]) -> Result<()> {
+    let path = dir.join(".views.json");
+    if views.is_empty() {
+        if path.is_file() {
+            remove_file(path)?;
+        }
+        return Ok(());
+    }
+    let json_array = Value::Array(views.iter().map(view_to_json).collect());
+    write(path, serde_json::to_string_pretty(&json_array)?)?;
+    Ok(())
+}
+
+
+fn view_to_json(view: &View) -> Value {
+    // Sorted so saving an unchanged view leaves the file unchanged in git
+    let mut filters: Vec<(&String, &ColumnFilter)> = view.column_filters.iter().collect();
+    filters.sort_by(|a, b| a.0.cmp(b.0));
+    let filters: Map<String, Value> = filters.into_iter()
+        .map(|(column, filter)| (column.clone(), match filter {
+            ColumnFilter::Contains(text) => json!({ "contains": text }),
+            ColumnFilter::Range { from, to } => json!({ "from": from, "to": to }),
+            ColumnFilter::Options(options) => json!({ "options": options }),
+        }))
+        .collect();
+    let sort: Vec<Value> = view.ordering.keys.iter()
+        .map(|(column, direction)| json!({
+            "column": column,
+            "direction": match direction {
+                SortDirection::Increasing => "asc",
+                SortDirection::Decreasing => "desc",
+            },
+        }))
+        .collect();
+
+    json!({
+        "name": view.name,
+        "query": view.query,
+        "filters": filters,
+        "sort": sort,
+        "empty_values": match view.ordering.empties {
+            EmptyPlacement::First => "first",
+            EmptyPlacement::Last => "last",
+        },
+        "hidden_columns": view.hidden_columns,
+        "group_by": view.group_by,
+    })
+}
+
+
+/// Reads a view, skipping parts it does not recognise so views written by newer versions still load
+fn view_from_json(value: &Value) -> Option<View> {
+    let text = |key: &str| value.get(key).and_then(|v| v.as_str()).map(String::from);
+    let strings = |value: Option<&Value>| -> Vec<String> {
+        value.and_then(|v| v.as_array())
+            .map(|items| items.iter().filter_map(|item| item.as_str().map(String::from)).collect())
+            .unwrap_or_default()
+    };
+
+    let column_filters = value.get("filters")
+        .and_then(|v| v.as_object())
+        .map(|filters| filters.iter()
+            .filter_map(|(column, filter)| {
+                let text = |key: &str| filter.get(key).and_then(|v| v.as_str()).map(String::from);
+                let parsed = match (text("contains"), filter.get("options")) {
+                    (Some(contains), _) => ColumnFilter::Contains(contains),
+                    (None, Some(options)) => ColumnFilter::Options(strings(Some(options))),
+                    (None, None) => ColumnFilter::Range {
+                        from: text("from").unwrap_or_default(),
+                        to: text("to").unwrap_or_default(),
+                    },
+                };
+                match parsed.is_empty() {
+                    true => None,
+                    false => Some((column.clone(), parsed)),
+                }
+            })
+            .collect())
+        .unwrap_or_default();
+    let keys = value.get("sort")
+        .and_then(|v| v.as_array())
+        .map(|keys| keys.iter()
+            .filter_map(|key| {
+                let column = key.get("column")?.as_str()?.to_string();
+                let direction = match key.get("direction").and_then(|v| v.as_str()) {
+                    Some("desc") => SortDirection::Decreasing,
+                    _ => SortDirection::Increasing,
+                };
+                Some((column, direction))
+            })
+            .collect())
+        .unwrap_or_default();
+    let empties = match text("empty_values").as_deref() {
+        Some("first") => EmptyPlacement::First,
+        _ => EmptyPlacement::Last,
+    };
+
+    Some(View {
+        name: text("name").filter(|name| !name.trim().is_empty())?,
+        query: text("query").unwrap_or_default(),
+        column_filters,
+        ordering: Order { keys, empties },
+        hidden_columns: strings(value.get("hidden_columns")),
+        group_by: text("group_by"),
+    })
+}
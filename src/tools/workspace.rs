@@ -0,0 +1,62 @@
+use crate::prelude::*;
+use std::{
+    fs::read_dir,
+    path::{Component, Path},
+};
+
+
+
+/// Collects every directory below `DOC_DIR` that holds a `.database.json`
+///
+/// Hidden directories (e.g. `.git`) and the `sys` database folder are skipped.
+pub fn database_dirs() -> Vec<PathBuf> {
+    let root = DOC_DIR.read().unwrap().clone();
+    let mut stack = vec![root.clone()];
+    let mut result = Vec::new();
+
+    while let Some(dir) = stack.pop() {
+        if dir.join(".database.json").is_file() {
+            result.push(dir.clone());
+        }
+        let entries = match read_dir(&dir) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+            let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            let skipped = name.starts_with('.') || (dir == root && name == "sys");
+            if path.is_dir() && !skipped {
+                stack.push(path);
+            }
+        }
+    }
+    result.sort();
+    result
+}
+
+
+/// Names a directory relative to `DOC_DIR`, with `/` standing for the workspace root
+pub fn relative_to_docs(path: &Path) -> Option<String> {
+    let base = DOC_DIR.read().ok()?.canonicalize().ok()?;
+    let canonical = path.canonicalize().ok()?;
+    let relative = canonical.strip_prefix(&base).ok()?.to_string_lossy().into_owned();
+    match relative.is_empty() {
+        true => Some("/".to_string()),
+        false => Some(relative),
+    }
+}
+
+
+/// Whether a path relative to `DOC_DIR` stays inside it
+///
+/// Only plain names are allowed: no `..`, root or drive prefix, which would leave the workspace.
+pub fn stays_inside(relative: &str) -> bool {
+    Path::new(relative).components().all(|part| matches!(part, Component::Normal(_) | Component::CurDir))
+}
+
+
+/// The canonical path of `DOC_DIR`, telling workspaces apart in state kept outside them
+pub fn identity() -> Result<String> {
+    let root = DOC_DIR.read().map_err(|e| Report::msg(e.to_string()))?.canonicalize()?;
+    Ok(root.to_string_lossy().into_owned())
+}
@@ -1,6 +1,9 @@
-use crate::types::input::InputField;
+use crate::types::{
+    input::{InputField, AttributeInfo},
+    value::FieldValue,
+};
 use std::path::PathBuf;
 
-pub type MetadataVec = Vec<Vec<String>>;
-pub type AttributeVec = Vec<(String, InputField)>;
+pub type MetadataVec = Vec<Vec<FieldValue>>;
+pub type AttributeVec = Vec<(String, InputField, AttributeInfo)>;
 pub type BreadcrumbVec = Vec<(PathBuf, String)>;
@@ -1,10 +1,22 @@
 #![allow(non_snake_case)]
 use crate::{
     prelude::*,
-    tools::compare,
+    tools::{
+        compare::{self, EmptyPlacement},
+        preferences,
+        query::{self, ColumnFilter, QueryError},
+        views::{self, View},
+        workspace,
+        migration::{self, SchemaDiff},
+        schema_parser::{self, Line, SchemaError},
+    },
+    types::value::legacy_options,
 };
+use nom::Offset;
+use serde_json::Value;
 use std::{
     fs::{read_to_string, read_dir},
+    path::Path,
 };
 
 
@@ -18,23 +30,84 @@ pub struct FileData {
     pub attributes: AttributeVec,
     pub breadcrumbs: BreadcrumbVec,
     pub ordering: Order,
+    pub schema_error: Option<SchemaError>,
+    pub schema_diff: SchemaDiff,
+    /// Whether `.database.json` still holds values in the untyped form of older versions
+    pub legacy_values: bool,
+    /// Text of the query bar
+    pub query: String,
+    pub query_error: Option<QueryError>,
+    /// Filters of the row under the table headers, by attribute title (`__ID` for the file name)
+    pub column_filters: HashMap<String, ColumnFilter>,
+    /// Indices into `metadata` of the rows passing the query and filters, in display order
+    pub visible: Vec<usize>,
+    /// Titles of the attributes left out of the table
+    pub hidden_columns: Vec<String>,
+    /// Title of the attribute rows are grouped by
+    pub group_by: Option<String>,
+    /// Saved views of the current directory, from its `.views.json`
+    pub views: Vec<View>,
+    /// Why `.views.json` could not be read; views are not saved until it is fixed, so nobody's are lost
+    pub views_error: Option<String>,
+    /// Name of the view last applied or saved
+    pub active_view: Option<String>,
+    /// Why the sort order could not be remembered for this directory
+    pub preference_error: Option<String>,
 }
 
 
-#[derive(Clone, Debug)]
+/// Sort keys of the table, most significant first
+///
+/// Columns are kept by title rather than position so the ordering survives schema edits. `__ID` stands
+/// for the file name column.
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Order {
-    direction: SortDirection,
-    id: usize,
+    pub keys: Vec<(String, SortDirection)>,
+    pub empties: EmptyPlacement,
 }
 
 
-#[derive(Clone, Debug)]
-enum SortDirection {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortDirection {
     Increasing,
     Decreasing,
 }
 
 
+impl SortDirection {
+    pub fn toggled(self) -> Self {
+        match self {
+            SortDirection::Increasing => SortDirection::Decreasing,
+            SortDirection::Decreasing => SortDirection::Increasing,
+        }
+    }
+}
+
+
+impl Order {
+    /// Position and direction of a column among the sort keys
+    pub fn key_of(&self, column: &str) -> Option<(usize, SortDirection)> {
+        self.keys.iter()
+            .position(|(title, _)| title == column)
+            .map(|i| (i, self.keys[i].1))
+    }
+
+
+    /// Applies a click on a column header
+    ///
+    /// A plain click sorts by that column alone, toggling its direction if it already was the only key.
+    /// With `additive`, the column is appended as a secondary key, or its direction toggled if already a key.
+    pub fn click(&mut self, column: &str, additive: bool) {
+        match (self.keys.iter().position(|(title, _)| title == column), additive) {
+            (Some(i), true) => self.keys[i].1 = self.keys[i].1.toggled(),
+            (None, true) => self.keys.push((column.to_string(), SortDirection::Increasing)),
+            (Some(0), false) if self.keys.len() == 1 => self.keys[0].1 = self.keys[0].1.toggled(),
+            (_, false) => self.keys = vec![(column.to_string(), SortDirection::Increasing)],
+        }
+    }
+}
+
+
 
 impl FileData {
     pub fn new() -> Self {
@@ -45,8 +118,22 @@ impl FileData {
             directories: Vec::new(),
             metadata: Vec::new(),
             breadcrumbs: Vec::new(),
-            ordering: Order {direction: SortDirection::Increasing, id: 0},
+            ordering: Order::default(),
+            schema_error: None,
+            schema_diff: SchemaDiff::default(),
+            legacy_values: false,
+            query: String::new(),
+            query_error: None,
+            column_filters: HashMap::new(),
+            visible: Vec::new(),
+            hidden_columns: Vec::new(),
+            group_by: None,
+            views: Vec::new(),
+            views_error: None,
+            active_view: None,
+            preference_error: None,
         };
+        files.ordering = preferences::load_sorting(&files.current_path);
         files.refresh();
         files
     }
@@ -63,22 +150,292 @@ impl FileData {
                 .map(|e| e.path())));
         self.directories = self.get_directories();
         self.breadcrumbs = self.get_breadcrumbs()?;
-        self.attributes = self.get_attributes()?;
-        self.metadata = self.get_metadata()?;
-        match self.ordering.direction {
-            SortDirection::Increasing => {
-                self.metadata.sort_by(|a, b| compare::increasing(a, b, self.ordering.id));
-            }
-            SortDirection::Decreasing => {
-                self.metadata.sort_by(|a, b| compare::decreasing(a, b, self.ordering.id));
+        // A broken schema is shown in place of the table rather than failing the whole refresh
+        match self.get_attributes() {
+            Ok(v) => {
+                self.attributes = v;
+                self.schema_error = None;
+            },
+            Err(e) => {
+                self.attributes = Vec::new();
+                self.schema_error = Some(e);
+            },
+        }
+        // Read once and shared by every step below; a directory without attributes shows no table
+        let objects = match self.attributes.is_empty() {
+            true => HashMap::new(),
+            false => json_processor::get_json_hashmap(&self.current_path.join(".database.json")).map_err(|e| e.to_string())?,
+        };
+        let options: Vec<Vec<String>> = self.attributes.iter().map(|(_, field, _)| legacy_options(field)).collect();
+        self.metadata = self.get_metadata(&objects, &options)?;
+        // `visible` indexes into `metadata`, so it is rebuilt before anything else can fail
+        self.sort();
+        self.filter();
+        // Old values are read in their typed form; the file is only rewritten once the user confirms
+        self.legacy_values = migration::needs_upgrade(&objects, &self.attributes, &options);
+        self.schema_diff = migration::diff(&objects, &self.attributes);
+        // A broken views file should not hide the entries
+        match views::load(&self.current_path) {
+            Ok(v) => {
+                self.views = v;
+                self.views_error = None;
+            },
+            Err(e) => {
+                self.views = Vec::new();
+                self.views_error = Some(format!("Views could not be read from .views.json. {e}"));
+            },
+        }
+        Ok(())
+    }
+
+
+    /// Sorts `metadata` by the current ordering, falling back to the file name between equal rows
+    fn sort(&mut self) {
+        // Column 0 holds the file name, so attribute columns are offset by one
+        let mut keys: Vec<(usize, Option<&InputField>, SortDirection)> = self.ordering.keys.iter()
+            .filter_map(|(title, direction)| match title.as_str() {
+                "__ID" => Some((0, None, *direction)),
+                _ => self.attributes.iter()
+                    .position(|(name, _, _)| name == title)
+                    .map(|i| (i + 1, Some(&self.attributes[i].1), *direction)),
+            })
+            .collect();
+        keys.push((0, None, SortDirection::Increasing));
+        let empties = self.ordering.empties;
+        self.metadata.sort_by(|a, b| {
+            keys.iter().fold(std::cmp::Ordering::Equal, |result, (id, field, direction)| {
+                result.then_with(|| match direction {
+                    SortDirection::Increasing => compare::increasing(a, b, *id, *field, empties),
+                    SortDirection::Decreasing => compare::decreasing(a, b, *id, *field, empties),
+                })
+            })
+        });
+    }
+
+
+    /// Sorts the table by a clicked column and remembers the ordering for the current directory, unless a view is applied
+    ///
+    /// # Props
+    /// - `column`: The attribute title, or `__ID` for the file name
+    /// - `additive`: Whether the column is added as a secondary key (shift-click)
+    pub fn sort_by(&mut self, column: &str, additive: bool) {
+        self.ordering.click(column, additive);
+        self.sort();
+        self.filter();
+        self.remember_sorting();
+    }
+
+
+    /// Moves rows with empty values to the top or bottom of the table and remembers the choice, unless a view is applied
+    pub fn set_empty_placement(&mut self, empties: EmptyPlacement) {
+        self.ordering.empties = empties;
+        self.sort();
+        self.filter();
+        self.remember_sorting();
+    }
+
+
+    /// Saves the ordering as the default of the current directory
+    ///
+    /// The ordering of an applied view belongs to the view, so it is not saved over the directory's own.
+    fn remember_sorting(&mut self) {
+        if self.active_view.is_some() {
+            return;
+        }
+        self.preference_error = preferences::save_sorting(&self.current_path, &self.ordering)
+            .err()
+            .map(|e| format!("Sort order could not be remembered. {e}"));
+    }
+
+
+    /// Computes `visible` from the query and column filters; `metadata` itself is left untouched
+    ///
+    /// An invalid query is reported in `query_error` and ignored, so the table keeps showing rows while typing.
+    fn filter(&mut self) {
+        let parsed = match self.query.trim().is_empty() {
+            true => Ok(None),
+            false => query::parse(&self.query, &self.attributes).map(Some),
+        };
+        let parsed = match parsed {
+            Ok(v) => {
+                self.query_error = None;
+                v
+            },
+            Err(e) => {
+                self.query_error = Some(e);
+                None
+            },
+        };
+        let filters: Vec<(usize, Option<&InputField>, &ColumnFilter)> = self.column_filters.iter()
+            .filter(|(_, filter)| !filter.is_empty())
+            .filter_map(|(title, filter)| query::column(&self.attributes, title).map(|(i, field)| (i, field, filter)))
+            .collect();
+
+        self.visible = self.metadata.iter()
+            .enumerate()
+            .filter(|(_, row)| parsed.as_ref().is_none_or(|q| q.matches(row, &self.attributes)))
+            .filter(|(_, row)| filters.iter().all(|(i, field, filter)| row.get(*i).is_some_and(|cell| filter.matches(cell, *field))))
+            .map(|(i, _)| i)
+            .collect();
+    }
+
+
+    pub fn set_query(&mut self, text: String) {
+        self.query = text;
+        self.filter();
+    }
+
+
+    /// Sets or clears the filter of a column
+    ///
+    /// # Props
+    /// - `column`: The attribute title, or `__ID` for the file name
+    /// - `filter`: The new filter; an empty one removes the column's filter
+    pub fn set_column_filter(&mut self, column: &str, filter: ColumnFilter) {
+        match filter.is_empty() {
+            true => self.column_filters.remove(column),
+            false => self.column_filters.insert(column.to_string(), filter),
+        };
+        self.filter();
+    }
+
+
+    /// Groups the table by a `One` or `Choice` attribute, or shows it flat with `None`
+    pub fn set_group_by(&mut self, column: Option<String>) {
+        self.group_by = column;
+    }
+
+
+    /// Splits the visible rows by the value of the `group_by` attribute
+    ///
+    /// Groups follow the order of the attribute's options (the rows of its sys database for `One`), and
+    /// values that are not options come after them. Rows without a value form the last group, labelled `""`.
+    ///
+    /// # Returns
+    /// Each group's label with its rows as indices into `metadata`, in display order
+    pub fn groups(&self) -> Vec<(String, Vec<usize>)> {
+        let (index, field) = match self.group_by.as_ref().and_then(|title| query::column(&self.attributes, title)) {
+            Some(v) => v,
+            None => return vec![(String::new(), self.visible.clone())],
+        };
+        let mut order: Vec<String> = match field {
+            Some(InputField::Choice { options, .. }) => options.clone(),
+            Some(InputField::One { id, .. }) => scroll_processor::db_query(id)
+                .map(|(rows, _)| rows.into_iter().skip(1).filter_map(|row| row.first().cloned()).collect())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+        for i in self.visible.iter() {
+            let label = self.metadata[*i].get(index).map(|v| v.to_string()).unwrap_or_default();
+            match groups.iter_mut().find(|(other, _)| *other == label) {
+                Some((_, rows)) => rows.push(*i),
+                None => groups.push((label, vec![*i])),
             }
+        }
+        let mut extra: Vec<String> = groups.iter()
+            .map(|(label, _)| label.clone())
+            .filter(|label| !label.is_empty() && !order.contains(label))
+            .collect();
+        extra.sort_by(|a, b| compare::natural(a, b));
+        order.extend(extra);
+        let rank = |label: &String| match label.is_empty() {
+            true => usize::MAX,
+            false => order.iter().position(|other| other == label).unwrap_or(usize::MAX - 1),
         };
+        groups.sort_by_key(|(label, _)| rank(label));
+        groups
+    }
+
+
+    /// Shows or hides an attribute column
+    pub fn toggle_column(&mut self, column: &str) {
+        match self.hidden_columns.iter().position(|title| title == column) {
+            Some(i) => {
+                self.hidden_columns.remove(i);
+            },
+            None => self.hidden_columns.push(column.to_string()),
+        }
+    }
+
+
+    /// Applies a saved view, or returns to the plain table with `None`
+    pub fn apply_view(&mut self, name: Option<&str>) {
+        let view = match name.and_then(|name| self.views.iter().find(|view| view.name == name)) {
+            Some(v) => v.clone(),
+            None => View {
+                ordering: preferences::load_sorting(&self.current_path),
+                ..View::default()
+            },
+        };
+        self.query = view.query;
+        self.column_filters = view.column_filters;
+        self.ordering = view.ordering;
+        self.hidden_columns = view.hidden_columns;
+        self.group_by = view.group_by;
+        self.active_view = name.map(String::from).filter(|_| !view.name.is_empty());
+        self.sort();
+        self.filter();
+    }
+
+
+    /// Refuses to write `.views.json` while it could not be read, as that would drop the views it holds
+    fn check_views(&self) -> Result<(), String> {
+        match &self.views_error {
+            Some(e) => Err(format!("{e} Fix or remove the file before changing views.")),
+            None => Ok(()),
+        }
+    }
+
+
+    /// Saves the current filters, sort, columns and grouping as a view, replacing one with the same name
+    pub fn save_view(&mut self, name: &str) -> Result<(), String> {
+        self.check_views()?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("A view needs a name.".to_string());
+        }
+        let view = View {
+            name: name.to_string(),
+            query: self.query.clone(),
+            column_filters: self.column_filters.clone(),
+            ordering: self.ordering.clone(),
+            hidden_columns: self.hidden_columns.clone(),
+            group_by: self.group_by.clone(),
+        };
+        let mut updated = self.views.clone();
+        match updated.iter().position(|other| other.name == name) {
+            Some(i) => updated[i] = view,
+            None => updated.push(view),
+        }
+        views::save(&self.current_path, &updated).map_err(|e| e.to_string())?;
+        self.views = updated;
+        self.active_view = Some(name.to_string());
+        Ok(())
+    }
+
+
+    pub fn delete_view(&mut self, name: &str) -> Result<(), String> {
+        self.check_views()?;
+        let updated: Vec<View> = self.views.iter()
+            .filter(|view| view.name != name)
+            .cloned()
+            .collect();
+        views::save(&self.current_path, &updated).map_err(|e| e.to_string())?;
+        self.views = updated;
+        if self.active_view.as_deref() == Some(name) {
+            self.active_view = None;
+        }
         Ok(())
     }
 
 
+    /// Empties what is read from disk, so a refresh that fails part way never pairs new rows with old indices
     fn clear(&mut self) {
         self.path_contents.clear();
+        self.metadata.clear();
+        self.visible.clear();
     }
 
 
@@ -108,53 +465,31 @@ impl FileData {
     }
 
 
-    fn get_attributes(&self) -> Result<AttributeVec, String> {
-        let attribute_file = self.current_path.join(".attributes.scroll");
-        let data = match read_to_string(&attribute_file) {
-            Ok(v) => v,
-            Err(_) => return Ok(Vec::new()),
-        };
-
-        let err_stub = format!("Attribute parsing failed. File: \"{}\".\n|ADDITIONAL INFO| ", attribute_file.display());
-        let err_report = |database, line_number| format!(
-            "{err_stub}\nLine: {line_number}.\nInternal: {database}.");
-
-        let trimmed_data: Vec<(&str, &str)> = scroll_processor::parse_pairs(&data)
-            .map_err(|e| err_report("".to_string(), e))?;
-
-        // Parse the components of each line
-        trimmed_data.iter().enumerate()
-            .map(|(i, (title, raw_type))| {
-                let line_num = i + 1;
-                let attr_type: InputField = scroll_processor::parse_attribute(raw_type)
-                    .map_err(|e| err_report(e, line_num))?;
-                Ok((title.to_string(), attr_type))
-            })
-            .collect()
+    fn get_attributes(&self) -> Result<AttributeVec, SchemaError> {
+        load_attributes(&self.current_path, &mut Vec::new())
     }
 
 
-    fn get_metadata(&self) -> Result<MetadataVec, String> {
-        if self.attributes.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        let db_path = self.current_path.join(".database.json");
-        let objects = json_processor::get_json_hashmap(&db_path).map_err(|e| e.to_string())?;
-
+    /// Reads the records into table rows, `__ID` first and then one value per attribute
+    ///
+    /// # Props
+    /// - `objects`: The records of `.database.json`, by `__ID`
+    /// - `options`: The [`legacy_options`] of each attribute, in schema order
+    fn get_metadata(&self, objects: &HashMap<String, HashMap<String, Value>>, options: &[Vec<String>]) -> Result<MetadataVec, String> {
         let result = objects.par_iter()
             .map(|(_, map)| {
-                let mut struct_metadata: Vec<String> = Vec::new();
+                let mut struct_metadata: Vec<FieldValue> = Vec::new();
                 struct_metadata.push(map
                     .get("__ID")
-                    .cloned()
+                    .map(|id| FieldValue::from_json(id, None))
                     .ok_or("ID field not found in JSON".to_string())?);
                 
-                for (attribute, _) in self.attributes.iter() {
+                // Attributes added after a record was written are shown empty until migrated
+                for ((attribute, field, _), options) in self.attributes.iter().zip(options.iter()) {
                     struct_metadata.push(map
                         .get(attribute)
-                        .cloned()
-                        .ok_or(format!("{attribute} field not found in JSON"))?);
+                        .map(|value| FieldValue::from_stored(value, field, options))
+                        .unwrap_or(FieldValue::empty(field)));
                 }
                 Ok(struct_metadata)
             })
@@ -163,10 +498,90 @@ impl FileData {
     }
 
 
-    pub fn goto(&mut self, path: &PathBuf) {
+    pub fn goto(&mut self, path: &Path) {
         assert!(path.is_dir(), "Attempted navigation to a non-directory file");
-        self.current_path = path.clone();
+        self.current_path = path.to_path_buf();
+        self.ordering = preferences::load_sorting(path);
+        // Filters name the columns of one directory, so they do not follow into another
+        self.query.clear();
+        self.column_filters.clear();
+        self.hidden_columns.clear();
+        self.group_by = None;
+        self.active_view = None;
+        self.preference_error = None;
         self.refresh();
     }
 }
 
+
+
+/// Reads the attribute schema of a directory, resolving `@extends` inheritance
+///
+/// # Props
+/// - `dir`: The directory holding `.attributes.scroll`
+/// - `visited`: Directories already on the inheritance chain, used to reject cycles
+///
+/// # Returns
+/// - `Ok` with the inherited attributes, minus `-Name` removals, with local declarations overriding or appended
+/// - `Err(e)` locating the first problem in any file on the chain
+pub fn load_attributes(dir: &PathBuf, visited: &mut Vec<PathBuf>) -> Result<AttributeVec, SchemaError> {
+    let attribute_file = dir.join(".attributes.scroll");
+    let data = match read_to_string(&attribute_file) {
+        Ok(v) => v,
+        Err(_) => return Ok(Vec::new()),
+    };
+    let err_report = |offset: usize, message: String| SchemaError::at(&attribute_file, &data, offset, message);
+
+    let canonical = dir.canonicalize().map_err(|e| err_report(0, e.to_string()))?;
+    if visited.contains(&canonical) {
+        return Err(err_report(0, "Schema inheritance loops back on itself".to_string()));
+    }
+    visited.push(canonical);
+
+    let lines = schema_parser::parse_schema(&data)
+        .map_err(|(offset, message)| err_report(offset, message))?;
+
+    // Start from the parent schema, if any
+    let mut attributes: AttributeVec = Vec::new();
+    for line in lines.iter() {
+        if let Line::Extends { dir: relative } = line {
+            let parent = dir.join(relative);
+            let origin = workspace::relative_to_docs(&parent)
+                .ok_or(err_report(data.offset(relative), format!("{relative} is not a directory inside the workspace")))?;
+            attributes = load_attributes(&parent, visited)?
+                .into_iter()
+                .map(|(title, field, mut info)| {
+                    if info.origin == AttributeOrigin::Local {
+                        info.origin = AttributeOrigin::Inherited(origin.clone());
+                    }
+                    (title, field, info)
+                })
+                .collect();
+        }
+    }
+
+    for line in lines.iter() {
+        if let Line::Remove { title, at } = line {
+            if !attributes.iter().any(|(existing, _, _)| existing == title) {
+                return Err(err_report(data.offset(at), format!("Cannot remove {title}, as it is not inherited")));
+            }
+            attributes.retain(|(existing, _, _)| existing != title);
+        }
+    }
+
+    // Apply local declarations, overriding inherited ones of the same name
+    for line in lines.into_iter() {
+        if let Line::Declaration { title, field, mut info } = line {
+            match attributes.iter_mut().find(|(existing, _, _)| *existing == title) {
+                Some(inherited) => {
+                    if let AttributeOrigin::Inherited(origin) | AttributeOrigin::Overridden(origin) = &inherited.2.origin {
+                        info.origin = AttributeOrigin::Overridden(origin.clone());
+                    }
+                    *inherited = (title, field, info);
+                },
+                None => attributes.push((title, field, info)),
+            }
+        }
+    }
+    Ok(attributes)
+}
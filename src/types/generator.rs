@@ -1,10 +1,15 @@
-use crate::types::statics::FILE_DATA;
+use crate::types::{
+    aliases::AttributeVec,
+    input::{AttributeInfo, InputField},
+    statics::FILE_DATA,
+    value::FieldValue,
+};
 use dioxus::prelude::*;
 
 #[derive(Clone, Debug)]
 pub struct FileGenerator {
     pub filename: String,
-    pub metadata: Vec<String>,
+    pub metadata: Vec<FieldValue>,
     pub state: CreatorState,
     pub editing: bool,
 }
@@ -13,14 +18,16 @@ impl FileGenerator {
     pub fn new() -> Self {
         FileGenerator {
             filename: String::new(),
-            metadata: vec![String::new(); FILE_DATA.read().attributes.len()],
+            metadata: FILE_DATA.read().attributes.iter()
+                .map(|(_, field, info)| initial_value(field, info))
+                .collect(),
             state: CreatorState::Ok,
             editing: false,
             
         }
     }
 
-    pub fn set_fields(&mut self, filename: String, metadata: Vec<String>, editing: bool) {
+    pub fn set_fields(&mut self, filename: String, metadata: Vec<FieldValue>, editing: bool) {
         self.filename = filename;
         self.metadata = metadata;
         self.state = CreatorState::Ok;
@@ -30,6 +37,26 @@ impl FileGenerator {
     pub fn refresh(&mut self) {
         *self = Self::new();
     }
+
+    /// Keeps an open form in step with a schema that changed underneath it
+    ///
+    /// Values are matched to attributes by title; new attributes start at their default.
+    pub fn remap(&mut self, previous: &AttributeVec) {
+        self.metadata = FILE_DATA.read().attributes.iter()
+            .map(|(title, field, info)| previous.iter()
+                .position(|(old, _, _)| old == title)
+                .and_then(|i| self.metadata.get(i).cloned())
+                .unwrap_or(initial_value(field, info)))
+            .collect();
+    }
+}
+
+
+fn initial_value(field: &InputField, info: &AttributeInfo) -> FieldValue {
+    match &info.default {
+        Some(default) => FieldValue::from_input(field, default),
+        None => FieldValue::empty(field),
+    }
 }
 
 
@@ -37,23 +64,41 @@ impl FileGenerator {
 #[derive(Clone, Debug)]
 pub enum CreatorState {
     Ok,
-    Err { error: Vec<String> },
+    /// `file_name` marks an invalid file name, kept apart from attribute errors so an attribute may share its title
+    Err { file_name: bool, error: Vec<(String, String)> },
 }
 
 
 impl CreatorState {
     pub fn file_error(&mut self) {
-        *self = CreatorState::Err { error: vec!["File Name".to_string()] };
+        match self {
+            CreatorState::Ok => {
+                *self = CreatorState::Err { file_name: true, error: Vec::new() };
+            },
+            CreatorState::Err { ref mut file_name, .. } => {
+                *file_name = true;
+            }
+        }
     }
 
-    pub fn component_error(&mut self, title: &str) {
+    pub fn component_error(&mut self, title: &str, message: &str) {
         match self {
             CreatorState::Ok => {
-                *self = CreatorState::Err { error: vec![title.to_string()] };
+                *self = CreatorState::Err { file_name: false, error: vec![(title.to_string(), message.to_string())] };
             },
-            CreatorState::Err { ref mut error } => {
-                error.push(title.to_string());
+            CreatorState::Err { ref mut error, .. } => {
+                error.push((title.to_string(), message.to_string()));
             }
         }
     }
+
+    /// Looks up the error message of a single field, if it has one
+    pub fn message(&self, title: &str) -> Option<String> {
+        match self {
+            CreatorState::Ok => None,
+            CreatorState::Err { error, .. } => error.iter()
+                .find(|(field, _)| field == title)
+                .map(|(_, message)| message.clone()),
+        }
+    }
 }
@@ -1,9 +1,31 @@
+use crate::{
+    tools::{links, scroll_processor, time},
+    types::value::FieldValue,
+};
+use regex::Regex;
+
+
+
 #[derive(Clone, Debug)]
 pub enum InputField {
-    String { req: bool },
+    String { req: bool, rules: StringRules },
+    Number { req: bool },
+    Bool { req: bool },
     Date { req: bool },
+    DateTime { req: bool },
+    Duration { req: bool },
     One { id: String, req: bool },
     Multi { id: String, req: bool },
+    Choice { options: Vec<String>, req: bool },
+    Choices { options: Vec<String>, req: bool },
+    Url { req: bool },
+    Doi { req: bool },
+    Ref { dir: String, req: bool },
+    Refs { dir: String, req: bool },
+    Created,
+    Modified,
+    Author,
+    Seq { pattern: String },
 }
 
 
@@ -12,10 +34,191 @@ impl InputField {
     pub fn is_req(&self) -> bool {
         match self {
             InputField::String { req, .. } => *req,
+            InputField::Number { req, .. } => *req,
+            InputField::Bool { req, .. } => *req,
             InputField::Date { req, .. } => *req,
+            InputField::DateTime { req, .. } => *req,
+            InputField::Duration { req, .. } => *req,
             InputField::One { req, .. } => *req,
             InputField::Multi { req, .. } => *req,
+            InputField::Choice { req, .. } => *req,
+            InputField::Choices { req, .. } => *req,
+            InputField::Url { req, .. } => *req,
+            InputField::Doi { req, .. } => *req,
+            InputField::Ref { req, .. } => *req,
+            InputField::Refs { req, .. } => *req,
+            InputField::Created | InputField::Modified | InputField::Author | InputField::Seq { .. } => false,
+        }
+    }
+
+
+    /// Checks if the field is filled by Scroll rather than the user
+    pub fn is_auto(&self) -> bool {
+        matches!(self, InputField::Created | InputField::Modified | InputField::Author | InputField::Seq { .. })
+    }
+
+
+    /// Checks if the field holds several selections, stored as a JSON array
+    pub fn is_list(&self) -> bool {
+        matches!(self, InputField::Multi { .. } | InputField::Choices { .. } | InputField::Refs { .. })
+    }
+
+
+    /// Checks a non-empty value against the format rules of the field
+    ///
+    /// References are checked against the current entries of their directory, so renamed or deleted targets fail.
+    ///
+    /// # Returns
+    /// - `Ok` if the value is valid
+    /// - `Err(e)` with a message describing the problem to the user
+    pub fn validate(&self, value: &FieldValue) -> Result<(), String> {
+        let text = value.to_string();
+        match self {
+            InputField::String { rules, .. } => rules.validate(&text),
+            InputField::Number { .. } => {
+                match value {
+                    FieldValue::Number(_) => Ok(()),
+                    _ => Err("Enter a number.".to_string()),
+                }
+            },
+            InputField::Bool { .. } => {
+                match value {
+                    FieldValue::Bool(_) => Ok(()),
+                    _ => Err("Enter yes or no.".to_string()),
+                }
+            },
+            InputField::Choice { options, .. } => {
+                match options.contains(&text) {
+                    true => Ok(()),
+                    false => Err(format!("\"{text}\" is not one of: {}.", options.join(", "))),
+                }
+            },
+            InputField::Choices { options, .. } => {
+                match value.items().into_iter()
+                    .find(|selection| !options.iter().any(|option| option == selection)) {
+                    None => Ok(()),
+                    Some(v) => Err(format!("\"{v}\" is not one of: {}.", options.join(", "))),
+                }
+            },
+            InputField::DateTime { .. } => {
+                time::parse_datetime(&text)
+                    .map(|_| ())
+                    .ok_or("Enter a valid date and time.".to_string())
+            },
+            InputField::Duration { .. } => {
+                time::parse_duration(&text)
+                    .map(|_| ())
+                    .ok_or("Enter a duration such as 2h30m or 45m.".to_string())
+            },
+            InputField::Url { .. } => {
+                match links::is_valid_url(&text) {
+                    true => Ok(()),
+                    false => Err("Enter a full web address starting with http:// or https://.".to_string()),
+                }
+            },
+            InputField::Doi { .. } => {
+                links::normalize_doi(&text)
+                    .map(|_| ())
+                    .ok_or("Enter a DOI such as 10.1000/xyz123.".to_string())
+            },
+            InputField::Ref { dir, .. } | InputField::Refs { dir, .. } => {
+                let ids = scroll_processor::ref_query(dir)?;
+                match value.items().into_iter()
+                    .find(|selection| !ids.iter().any(|id| id == selection)) {
+                    None => Ok(()),
+                    Some(v) => Err(format!("\"{v}\" no longer exists in {dir}.")),
+                }
+            },
+            _ => Ok(()),
         }
     }
+
+
+    /// Converts a value to the form it is stored in, e.g. canonical DOI links or ISO-8601 times
+    pub fn normalize(&self, value: FieldValue) -> FieldValue {
+        let text = value.to_string();
+        match self {
+            InputField::DateTime { .. } => FieldValue::Date(time::datetime_to_iso(&text).unwrap_or(text)),
+            InputField::Duration { .. } => FieldValue::Text(time::duration_to_iso(&text).unwrap_or(text)),
+            InputField::Doi { .. } => FieldValue::Text(links::normalize_doi(&text).unwrap_or(text)),
+            InputField::Url { .. } => FieldValue::Text(text.trim().to_string()),
+            _ => value,
+        }
+    }
+
+
+    /// Formats a stored value for the file table
+    pub fn display(&self, value: &FieldValue) -> String {
+        match (self, value) {
+            (InputField::DateTime { .. } | InputField::Created | InputField::Modified, _) => time::datetime_to_display(&value.to_string()),
+            (InputField::Duration { .. }, _) => time::duration_to_display(&value.to_string()),
+            (_, FieldValue::Bool(true)) => "Yes".to_string(),
+            (_, FieldValue::Bool(false)) => "No".to_string(),
+            _ => value.to_string(),
+        }
+    }
+}
+
+
+
+/// Details of an attribute that shape the creation form but not the stored type
+///
+/// Declared after the type, e.g. `Vendor: String = "Benchling" [placeholder="Company", help="Where it was ordered"]`
+#[derive(Clone, Debug, Default)]
+pub struct AttributeInfo {
+    pub default: Option<String>,
+    pub placeholder: Option<String>,
+    pub help: Option<String>,
+    pub origin: AttributeOrigin,
 }
 
+
+/// Where an attribute of an inheriting schema (`@extends ..`) was declared
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum AttributeOrigin {
+    #[default]
+    Local,
+    Inherited(String),
+    Overridden(String),
+}
+
+
+impl AttributeOrigin {
+    /// Short label for the UI, naming the directory the attribute comes from
+    pub fn label(&self) -> Option<String> {
+        match self {
+            AttributeOrigin::Local => None,
+            AttributeOrigin::Inherited(dir) => Some(format!("from {dir}")),
+            AttributeOrigin::Overridden(dir) => Some(format!("overrides {dir}")),
+        }
+    }
+}
+
+
+/// Optional format rules of a `String` attribute, e.g. `String(pattern="^[A-Z]{2}\d{4}$", max=6)`
+#[derive(Clone, Debug, Default)]
+pub struct StringRules {
+    pub pattern: Option<Regex>,
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+    pub message: Option<String>,
+}
+
+
+
+impl StringRules {
+    /// Checks a value against every rule, preferring the custom message when one is set
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        let length = value.chars().count();
+        let default_message = if self.min.is_some_and(|min| length < min) {
+            format!("Must be at least {} characters long.", self.min.unwrap())
+        } else if self.max.is_some_and(|max| length > max) {
+            format!("Must be at most {} characters long.", self.max.unwrap())
+        } else if self.pattern.as_ref().is_some_and(|pattern| !pattern.is_match(value)) {
+            format!("Must match the pattern {}.", self.pattern.as_ref().unwrap().as_str())
+        } else {
+            return Ok(());
+        };
+        Err(self.message.clone().unwrap_or(default_message))
+    }
+}
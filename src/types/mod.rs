@@ -3,3 +3,4 @@ pub mod generator;
 pub mod files;
 pub mod input;
 pub mod statics;
+pub mod value;
@@ -10,6 +10,18 @@ pub static DATABASE_HOLD: LazyLock<RwLock<HashMap<String, (Vec<Vec<String>>, Vec
 );
 
 
+/// Problems found while reading the tables in `DOC_DIR/sys` that did not stop them from loading, by table
+pub static DATABASE_WARNINGS: LazyLock<RwLock<HashMap<String, Vec<String>>>> = LazyLock::new(
+    || RwLock::new(HashMap::new())
+);
+
+
 pub static FILE_DATA: GlobalSignal<FileData> = Global::new(|| FileData::new());
 
 pub static POPUP_GENERATOR: GlobalSignal<FileGenerator> = Global::new(|| FileGenerator::new());
+
+/// Problems the workspace watcher ran into, by what it was reloading, so they can be shown until resolved
+pub static WATCHER_ERRORS: GlobalSignal<HashMap<String, String>> = Global::new(HashMap::new);
+
+/// Why the search index could not be brought up to date, shown in the search popup
+pub static SEARCH_ERROR: GlobalSignal<Option<String>> = Global::new(|| None);
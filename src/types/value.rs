@@ -0,0 +1,200 @@
+use crate::{
+    tools::scroll_processor,
+    types::input::InputField,
+};
+use serde_json::{Number, Value};
+use std::fmt;
+
+
+
+/// A typed attribute value, as stored in `.database.json` and shown in the file table
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    Date(String),
+    List(Vec<String>),
+}
+
+
+
+impl Default for FieldValue {
+    fn default() -> Self {
+        FieldValue::Text(String::new())
+    }
+}
+
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Text(v) | FieldValue::Date(v) => write!(f, "{v}"),
+            FieldValue::Number(v) => write!(f, "{v}"),
+            FieldValue::Bool(v) => write!(f, "{v}"),
+            FieldValue::List(v) => write!(f, "{}", v.join(", ")),
+        }
+    }
+}
+
+
+impl FieldValue {
+    /// The value of an attribute nobody has filled in yet
+    pub fn empty(field: &InputField) -> Self {
+        match field {
+            InputField::Multi { .. } | InputField::Choices { .. } | InputField::Refs { .. } => FieldValue::List(Vec::new()),
+            InputField::Bool { .. } => FieldValue::Bool(false),
+            _ => FieldValue::Text(String::new()),
+        }
+    }
+
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            FieldValue::Text(v) | FieldValue::Date(v) => v.is_empty(),
+            FieldValue::List(v) => v.is_empty(),
+            FieldValue::Number(_) | FieldValue::Bool(_) => false,
+        }
+    }
+
+
+    /// The separate selections of a list, or the value itself for any other non-empty value
+    pub fn items(&self) -> Vec<String> {
+        match self {
+            FieldValue::List(v) => v.clone(),
+            v if v.is_empty() => Vec::new(),
+            v => vec![v.to_string()],
+        }
+    }
+
+
+    /// Reads a JSON value, using the attribute type to recognise dates and upgrade legacy strings
+    ///
+    /// Older databases stored every value as a string, with list selections joined by ", ".
+    ///
+    /// # Props
+    /// - `value`: The stored JSON value
+    /// - `field`: The attribute type, if the value belongs to a declared attribute
+    pub fn from_json(value: &Value, field: Option<&InputField>) -> Self {
+        FieldValue::read_json(value, field, |field, text| FieldValue::from_legacy(field, text, &legacy_options(field)))
+    }
+
+
+    /// Same as [`FieldValue::from_json`], with the options of the attribute looked up by the caller
+    ///
+    /// Reading a whole column this way queries its sys database or referenced directory only once.
+    ///
+    /// # Props
+    /// - `options`: What [`legacy_options`] returns for `field`
+    pub fn from_stored(value: &Value, field: &InputField, options: &[String]) -> Self {
+        FieldValue::read_json(value, Some(field), |field, text| FieldValue::from_legacy(field, text, options))
+    }
+
+
+    fn read_json(value: &Value, field: Option<&InputField>, legacy: impl FnOnce(&InputField, &str) -> Self) -> Self {
+        match (value, field) {
+            (Value::Null, Some(field)) => FieldValue::empty(field),
+            (Value::Null, None) => FieldValue::default(),
+            (Value::String(v), Some(field)) => legacy(field, v),
+            (Value::String(v), None) => FieldValue::Text(v.clone()),
+            (Value::Number(v), _) => v.as_f64().map(FieldValue::Number).unwrap_or(FieldValue::Text(v.to_string())),
+            (Value::Bool(v), _) => FieldValue::Bool(*v),
+            (Value::Array(v), _) => {
+                let items: Vec<String> = v.iter()
+                    .map(|item| match item {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect();
+                match field.is_some_and(|field| !field.is_list()) {
+                    true => FieldValue::Text(items.join(", ")),
+                    false => FieldValue::List(items),
+                }
+            },
+            (Value::Object(_), _) => FieldValue::Text(value.to_string()),
+        }
+    }
+
+
+    /// Reads a string stored by an older version of Scroll
+    ///
+    /// List selections were joined by ", ", which is ambiguous when an option contains a comma. Runs of
+    /// pieces that together form one of `options` are kept whole; every other piece becomes an item of its own.
+    pub fn from_legacy(field: &InputField, text: &str, options: &[String]) -> Self {
+        if !matches!(field, InputField::Choices { .. } | InputField::Multi { .. } | InputField::Refs { .. }) {
+            return FieldValue::from_input(field, text);
+        }
+        let pieces: Vec<&str> = text.split(", ").collect();
+        let mut items = Vec::new();
+        let mut start = 0;
+        while start < pieces.len() {
+            // The longest run forming a known option wins, e.g. "Tris, pH 8" over "Tris"
+            let end = (start + 1..=pieces.len()).rev()
+                .find(|end| options.iter().any(|option| *option == pieces[start..*end].join(", ")))
+                .unwrap_or(start + 1);
+            items.push(pieces[start..end].join(", ").trim().to_string());
+            start = end;
+        }
+        items.retain(|item| !item.is_empty());
+        FieldValue::List(items)
+    }
+
+
+    /// Reads text typed by a person, e.g. a schema default or a form input
+    ///
+    /// List selections are separated by `|`, as in `Choices(...)` declarations, so items may contain commas.
+    /// Text that does not fit a number or boolean attribute is kept as text, so validation can report it
+    /// instead of the value being lost.
+    pub fn from_input(field: &InputField, text: &str) -> Self {
+        match field {
+            InputField::Multi { .. } | InputField::Choices { .. } | InputField::Refs { .. } => {
+                FieldValue::List(text.split('|')
+                    .map(|item| item.trim().to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect())
+            },
+            InputField::Number { .. } if !text.trim().is_empty() => {
+                text.trim().parse::<f64>()
+                    .map(FieldValue::Number)
+                    .unwrap_or(FieldValue::Text(text.to_string()))
+            },
+            InputField::Bool { .. } => match text.trim().to_lowercase().as_str() {
+                "true" | "yes" | "1" => FieldValue::Bool(true),
+                "false" | "no" | "0" | "" => FieldValue::Bool(false),
+                _ => FieldValue::Text(text.to_string()),
+            },
+            InputField::Date { .. } | InputField::DateTime { .. } | InputField::Created | InputField::Modified => {
+                FieldValue::Date(text.to_string())
+            },
+            _ => FieldValue::Text(text.to_string()),
+        }
+    }
+
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            FieldValue::Text(v) | FieldValue::Date(v) => Value::String(v.clone()),
+            // Whole numbers are written without a trailing ".0"
+            FieldValue::Number(v) if v.fract() == 0.0 && v.abs() < i64::MAX as f64 => Value::Number(Number::from(*v as i64)),
+            FieldValue::Number(v) => Number::from_f64(*v).map(Value::Number).unwrap_or(Value::Null),
+            FieldValue::Bool(v) => Value::Bool(*v),
+            FieldValue::List(v) => Value::Array(v.iter().cloned().map(Value::String).collect()),
+        }
+    }
+}
+
+
+
+/// The values a list attribute can hold: its choices, the keys of its sys database or the entries it references
+///
+/// Used to tell commas inside an option from the separators of legacy values. Other attributes have none.
+pub fn legacy_options(field: &InputField) -> Vec<String> {
+    match field {
+        InputField::Choices { options, .. } => options.clone(),
+        InputField::Multi { id, .. } => scroll_processor::db_query(id)
+            .map(|(rows, _)| rows.into_iter().skip(1).filter_map(|row| row.first().cloned()).collect())
+            .unwrap_or_default(),
+        InputField::Refs { dir, .. } => scroll_processor::ref_query(dir).unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
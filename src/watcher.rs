@@ -0,0 +1,188 @@
+use crate::{
+    prelude::*,
+    db_popup::CurrentDB,
+    search_popup,
+    tools::search,
+};
+use notify::{
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+    event::{CreateKind, RemoveKind},
+};
+use std::{collections::HashSet, time::Duration};
+use tokio::{sync::mpsc, time::timeout};
+
+
+
+/// How long the workspace has to be quiet before changes are applied, so a `git pull` causes a single refresh
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+
+
+/// Parts of the workspace touched by a burst of file system events
+#[derive(Debug, Default)]
+struct Changes {
+    /// Names of sys databases that were edited, created or removed
+    databases: HashSet<String>,
+    /// Directories whose entries, records or schema changed
+    directories: HashSet<PathBuf>,
+    /// Whether any `.attributes.scroll` changed, which may affect inheriting directories
+    schema: bool,
+}
+
+
+impl Changes {
+    fn record(&mut self, event: Event, doc_dir: &PathBuf) {
+        let folder_event = matches!(event.kind, EventKind::Create(CreateKind::Folder) | EventKind::Remove(RemoveKind::Folder));
+        for path in event.paths {
+            let relative = match path.strip_prefix(doc_dir) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+            if relative.components().any(|part| part.as_os_str().to_string_lossy().starts_with(".git")) {
+                continue;
+            }
+            let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            let extension = path.extension().unwrap_or_default().to_string_lossy().into_owned();
+            let parent = path.parent().map(PathBuf::from).unwrap_or(doc_dir.clone());
+
+            if parent == doc_dir.join("sys") && ["scroll", "csv", "tsv"].contains(&extension.as_str()) {
+                self.databases.insert(path.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+            } else if name == ".attributes.scroll" {
+                self.schema = true;
+                self.directories.insert(parent);
+            } else if name == ".database.json" || name == ".views.json" || extension == "md" || folder_event {
+                self.directories.insert(parent);
+            }
+        }
+    }
+
+
+    fn is_empty(&self) -> bool {
+        self.databases.is_empty() && self.directories.is_empty() && !self.schema
+    }
+}
+
+
+
+/// Watches `DOC_DIR` for changes made outside Scroll, e.g. by Git or a Markdown editor
+///
+/// Runs for as long as the calling component is mounted. Open dialogs stay open; a form whose schema
+/// changed keeps the values of attributes that still exist.
+pub fn use_workspace_watcher() {
+    let current_db = use_context::<CurrentDB>();
+    use_hook(|| {
+        spawn(async move {
+            if let Err(e) = watch(current_db).await {
+                report("watcher", Err(format!("Changes made outside Scroll are no longer picked up. {e}")));
+            }
+        });
+    });
+}
+
+
+async fn watch(current_db: CurrentDB) -> Result<()> {
+    let doc_dir = DOC_DIR.read().unwrap().clone();
+    let (sender, mut receiver) = mpsc::unbounded_channel::<Event>();
+    let mut watcher = RecommendedWatcher::new(
+        move |event: notify::Result<Event>| {
+            if let Ok(event) = event {
+                let _ = sender.send(event);
+            }
+        },
+        notify::Config::default(),
+    )?;
+    watcher.watch(&doc_dir, RecursiveMode::Recursive)?;
+
+    while let Some(event) = receiver.recv().await {
+        let mut changes = Changes::default();
+        changes.record(event, &doc_dir);
+        loop {
+            match timeout(DEBOUNCE, receiver.recv()).await {
+                Ok(Some(event)) => changes.record(event, &doc_dir),
+                Ok(None) => return Ok(()),
+                Err(_) => break,
+            }
+        }
+        if !changes.is_empty() {
+            apply(changes, &current_db);
+        }
+    }
+    Ok(())
+}
+
+
+/// Reloads the state affected by a burst of changes
+fn apply(changes: Changes, current_db: &CurrentDB) {
+    for name in changes.databases.iter() {
+        report(&format!("sys/{name}"), scroll_processor::reload_db(name).map_err(|e| e.to_string()));
+    }
+    reset_removed_db(current_db);
+
+    // Indexing reads every entry of a directory, so it stays off the UI thread
+    let directories: Vec<PathBuf> = changes.directories.iter().cloned().collect();
+    if !directories.is_empty() {
+        spawn(async move {
+            let result = tokio::task::spawn_blocking(move || match directories.iter().all(|dir| dir.is_dir()) {
+                true => directories.iter().try_for_each(search::update_dir),
+                false => search::sync().map(|_| ()),
+            }).await;
+            search_popup::report_indexing(result);
+        });
+    }
+
+    let current_path = FILE_DATA.read().current_path.clone();
+    let affected = !changes.databases.is_empty()
+        || changes.schema
+        || changes.directories.contains(&current_path)
+        || !current_path.is_dir();
+    if !affected {
+        return;
+    }
+
+    // The open directory may have been removed, e.g. by switching branches
+    let mut target = current_path.clone();
+    while !target.is_dir() && target.pop() {}
+
+    let previous = FILE_DATA.read().attributes.clone();
+    let refreshed = match target == current_path {
+        true => FILE_DATA.write().refresh(),
+        false => {
+            FILE_DATA.write().goto(&target);
+            Ok(())
+        },
+    };
+    report("directory", refreshed.map_err(|e| format!("The open directory could not be reloaded. {e}")));
+    POPUP_GENERATOR.write().remap(&previous);
+}
+
+
+/// Points the database popup at another table once its own is gone, e.g. after a `git checkout`
+fn reset_removed_db(current_db: &CurrentDB) {
+    let CurrentDB(mut name, mut target) = current_db.clone();
+    if DATABASE_HOLD.read().unwrap().contains_key(&*name.peek()) {
+        return;
+    }
+    let mut remaining: Vec<String> = DATABASE_HOLD.read().unwrap().keys().cloned().collect();
+    remaining.sort();
+    if let Some(first) = remaining.into_iter().next() {
+        name.set(first);
+    }
+    target.set(None);
+    // The popup is not mounted on the home page
+    document::eval(r#"document.getElementById("db-popup")?.close();"#);
+}
+
+
+/// Keeps the outcome of reloading one part of the workspace in `WATCHER_ERRORS`, clearing it once it works again
+fn report(source: &str, result: Result<(), String>) {
+    match result {
+        Ok(()) => {
+            if WATCHER_ERRORS.read().contains_key(source) {
+                WATCHER_ERRORS.write().remove(source);
+            }
+        },
+        Err(e) => {
+            WATCHER_ERRORS.write().insert(source.to_string(), e);
+        },
+    }
+}